@@ -0,0 +1,48 @@
+use std::error::Error;
+use std::fmt::{self, Display};
+
+use crate::lexer::token::Operator;
+use crate::span::Span;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DerivativeError {
+    /// A call to a function without a known derivative, such as a user function.
+    UnsupportedFunction(String, Span),
+    /// A comparison or logical operator, whose result is not differentiable.
+    UnsupportedOperator(Operator, Span),
+    /// A factorial of an expression in the named variable, which is only defined for whole
+    /// numbers and so not differentiable.
+    UnsupportedFactorial(String, Span),
+}
+
+impl DerivativeError {
+    /// Returns the part of the input the error originates from.
+    pub fn span(&self) -> Span {
+        match self {
+            DerivativeError::UnsupportedFunction(_, span)
+            | DerivativeError::UnsupportedOperator(_, span)
+            | DerivativeError::UnsupportedFactorial(_, span) => *span,
+        }
+    }
+}
+
+impl Display for DerivativeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DerivativeError::UnsupportedFunction(name, _) => {
+                write!(f, "cannot differentiate function `{name}`")
+            }
+            DerivativeError::UnsupportedOperator(operator, _) => {
+                write!(f, "cannot differentiate operator `{operator}`")
+            }
+            DerivativeError::UnsupportedFactorial(variable, _) => {
+                write!(
+                    f,
+                    "cannot differentiate a factorial with respect to `{variable}`"
+                )
+            }
+        }
+    }
+}
+
+impl Error for DerivativeError {}
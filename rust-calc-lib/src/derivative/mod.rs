@@ -0,0 +1,237 @@
+pub mod error;
+#[cfg(test)]
+mod tests;
+
+use crate::derivative::error::DerivativeError;
+use crate::lexer::token::Operator;
+use crate::numeric::NumericValue;
+use crate::optimizer::optimize;
+use crate::parser::ast::{Expression, ExpressionKind, UnaryOp};
+
+/// Differentiates `expression` with respect to the variable `variable`, treating every other
+/// name as a constant, and simplifies the result with [`optimize`].
+///
+/// Besides the arithmetic operators, calls to `sin`, `cos`, `tan`, `exp`, `ln` and `sqrt` are
+/// differentiated by the chain rule, and conditionals branch by branch. Any part of the
+/// expression without `variable` in it is a constant, whatever it contains, so only other
+/// functions and operators applied to `variable` are an error.
+pub fn diff<N: NumericValue>(
+    expression: &Expression<N>,
+    variable: &str,
+) -> Result<Expression<N>, DerivativeError> {
+    Ok(optimize(derivative(expression, variable)?))
+}
+
+fn derivative<N: NumericValue>(
+    expression: &Expression<N>,
+    variable: &str,
+) -> Result<Expression<N>, DerivativeError> {
+    if !depends_on(expression, variable) {
+        return Ok(number(N::zero()));
+    }
+    let d = |expression: &Expression<N>| derivative(expression, variable);
+    Ok(match &expression.kind {
+        ExpressionKind::Variable(name) if name == variable => number(N::one()),
+        ExpressionKind::Number(_) | ExpressionKind::Variable(_) | ExpressionKind::ResultRef(_) => {
+            number(N::zero())
+        }
+        ExpressionKind::Unary(UnaryOp::Negative, operand) => neg(d(operand)?),
+        ExpressionKind::Unary(UnaryOp::Positive, operand) => d(operand)?,
+        ExpressionKind::Unary(UnaryOp::Percent, operand) => {
+            ExpressionKind::Unary(UnaryOp::Percent, Box::new(d(operand)?)).into()
+        }
+        ExpressionKind::Unary(UnaryOp::Not, _) => {
+            return Err(DerivativeError::UnsupportedOperator(
+                Operator::Not,
+                expression.span,
+            ));
+        }
+        ExpressionKind::Unary(UnaryOp::Factorial, _) => {
+            return Err(DerivativeError::UnsupportedFactorial(
+                variable.to_owned(),
+                expression.span,
+            ));
+        }
+        ExpressionKind::Unary(UnaryOp::_Marker(_), _) => unreachable!(),
+        ExpressionKind::Binary(u, operator, v) => {
+            let (u, v) = (u.as_ref().clone(), v.as_ref().clone());
+            match operator {
+                Operator::Plus => add(d(&u)?, d(&v)?),
+                Operator::Minus => sub(d(&u)?, d(&v)?),
+                Operator::Star => add(mul(d(&u)?, v.clone()), mul(u.clone(), d(&v)?)),
+                Operator::Slash => match d(&v)? {
+                    dv if is_zero(&dv) => div(d(&u)?, v),
+                    dv => div(
+                        sub(mul(d(&u)?, v.clone()), mul(u, dv)),
+                        pow(v, number(N::one() + N::one())),
+                    ),
+                },
+                Operator::Caret => power_derivative(u, v, variable)?,
+                _ => {
+                    return Err(DerivativeError::UnsupportedOperator(
+                        *operator,
+                        expression.span,
+                    ));
+                }
+            }
+        }
+        ExpressionKind::Conditional(condition, then_branch, else_branch) => {
+            ExpressionKind::Conditional(
+                condition.clone(),
+                Box::new(d(then_branch)?),
+                Box::new(d(else_branch)?),
+            )
+            .into()
+        }
+        ExpressionKind::Call(name, arguments) => match arguments.as_slice() {
+            [u] => mul(outer_derivative(name, u, expression)?, d(u)?),
+            _ => {
+                return Err(DerivativeError::UnsupportedFunction(
+                    name.clone(),
+                    expression.span,
+                ));
+            }
+        },
+    })
+}
+
+/// Whether `variable` occurs in `expression`.
+fn depends_on<N: NumericValue>(expression: &Expression<N>, variable: &str) -> bool {
+    match &expression.kind {
+        ExpressionKind::Variable(name) => name == variable,
+        ExpressionKind::Number(_) | ExpressionKind::ResultRef(_) => false,
+        ExpressionKind::Unary(_, operand) => depends_on(operand, variable),
+        ExpressionKind::Binary(left, _, right) => {
+            depends_on(left, variable) || depends_on(right, variable)
+        }
+        ExpressionKind::Conditional(condition, then_branch, else_branch) => {
+            [condition, then_branch, else_branch]
+                .into_iter()
+                .any(|expression| depends_on(expression, variable))
+        }
+        ExpressionKind::Call(_, arguments) => arguments
+            .iter()
+            .any(|argument| depends_on(argument, variable)),
+    }
+}
+
+/// The derivative of `u ^ v`, using the power rule when the exponent is constant and the rule
+/// for exponentials when the base is.
+fn power_derivative<N: NumericValue>(
+    u: Expression<N>,
+    v: Expression<N>,
+    variable: &str,
+) -> Result<Expression<N>, DerivativeError> {
+    let (du, dv) = (derivative(&u, variable)?, derivative(&v, variable)?);
+    if is_zero(&dv) {
+        let lowered = sub(v.clone(), number(N::one()));
+        return Ok(mul(mul(v, pow(u, lowered)), du));
+    }
+    if is_zero(&du) {
+        return Ok(mul(mul(pow(u.clone(), v), call("ln", u)), dv));
+    }
+    // d(u^v) = u^v * (v' * ln(u) + v * u' / u)
+    let inner = add(
+        mul(dv, call("ln", u.clone())),
+        div(mul(v.clone(), du), u.clone()),
+    );
+    Ok(mul(pow(u, v), inner))
+}
+
+/// The derivative of the builtin `name` evaluated at its argument `u`, which is multiplied by
+/// the derivative of `u` for the chain rule.
+fn outer_derivative<N: NumericValue>(
+    name: &str,
+    u: &Expression<N>,
+    call_expression: &Expression<N>,
+) -> Result<Expression<N>, DerivativeError> {
+    let u = u.clone();
+    let two = || number(N::one() + N::one());
+    Ok(match name {
+        "sin" => call("cos", u),
+        "cos" => neg(call("sin", u)),
+        "tan" => div(number(N::one()), pow(call("cos", u), two())),
+        "exp" => call("exp", u),
+        "ln" => div(number(N::one()), u),
+        "sqrt" => div(number(N::one()), mul(two(), call("sqrt", u))),
+        _ => {
+            return Err(DerivativeError::UnsupportedFunction(
+                name.to_owned(),
+                call_expression.span,
+            ));
+        }
+    })
+}
+
+// The constructors below leave out terms that are zero because a derivative is, which keeps
+// the result readable. This is not something `optimize` may do in general, since `x * 0` is
+// NaN rather than zero for an infinite `x`.
+
+fn number<N: NumericValue>(value: N) -> Expression<N> {
+    ExpressionKind::Number(value).into()
+}
+
+fn is_zero<N: NumericValue>(expression: &Expression<N>) -> bool {
+    matches!(&expression.kind, ExpressionKind::Number(value) if value.is_zero())
+}
+
+fn is_one<N: NumericValue>(expression: &Expression<N>) -> bool {
+    matches!(&expression.kind, ExpressionKind::Number(value) if value.is_one())
+}
+
+fn binary<N: NumericValue>(
+    a: Expression<N>,
+    operator: Operator,
+    b: Expression<N>,
+) -> Expression<N> {
+    ExpressionKind::Binary(Box::new(a), operator, Box::new(b)).into()
+}
+
+fn call<N: NumericValue>(name: &str, argument: Expression<N>) -> Expression<N> {
+    ExpressionKind::Call(name.to_owned(), vec![argument]).into()
+}
+
+fn neg<N: NumericValue>(a: Expression<N>) -> Expression<N> {
+    if is_zero(&a) {
+        return a;
+    }
+    ExpressionKind::Unary(UnaryOp::Negative, Box::new(a)).into()
+}
+
+fn add<N: NumericValue>(a: Expression<N>, b: Expression<N>) -> Expression<N> {
+    match (is_zero(&a), is_zero(&b)) {
+        (true, _) => b,
+        (_, true) => a,
+        _ => binary(a, Operator::Plus, b),
+    }
+}
+
+fn sub<N: NumericValue>(a: Expression<N>, b: Expression<N>) -> Expression<N> {
+    match (is_zero(&a), is_zero(&b)) {
+        (_, true) => a,
+        (true, _) => neg(b),
+        _ => binary(a, Operator::Minus, b),
+    }
+}
+
+fn mul<N: NumericValue>(a: Expression<N>, b: Expression<N>) -> Expression<N> {
+    if is_zero(&a) || is_zero(&b) {
+        return number(N::zero());
+    }
+    match (is_one(&a), is_one(&b)) {
+        (true, _) => b,
+        (_, true) => a,
+        _ => binary(a, Operator::Star, b),
+    }
+}
+
+fn div<N: NumericValue>(a: Expression<N>, b: Expression<N>) -> Expression<N> {
+    if is_zero(&a) || is_one(&b) {
+        return a;
+    }
+    binary(a, Operator::Slash, b)
+}
+
+fn pow<N: NumericValue>(a: Expression<N>, b: Expression<N>) -> Expression<N> {
+    binary(a, Operator::Caret, b)
+}
@@ -0,0 +1,156 @@
+use std::collections::HashMap;
+
+use crate::derivative::diff;
+use crate::derivative::error::DerivativeError;
+use crate::evaluator::Evaluator;
+use crate::lexer::token::Operator;
+use crate::numeric::float::FloatBuiltins;
+use crate::parser::Parser;
+use crate::parser::ast::{Expression, Statement};
+use crate::span::Span;
+
+fn expression(input: &str) -> Expression<f64> {
+    match Parser::new(input).parse_program().unwrap().pop() {
+        Some(Statement::Expression(expression)) => expression,
+        statement => panic!("`{input}` is not an expression: {statement:?}"),
+    }
+}
+
+/// Macro to generate tests of the simplified derivative with respect to `x`
+macro_rules! derivative_test {
+    ($name:ident, $input:expr, $expected:expr) => {
+        #[test]
+        fn $name() {
+            assert_eq!(
+                diff(&expression($input), "x").unwrap(),
+                expression($expected),
+                "Derivative mismatch in test '{}'\ninput: {}",
+                stringify!($name),
+                $input,
+            );
+        }
+    };
+}
+
+derivative_test!(constant, "42", "0");
+derivative_test!(variable, "x", "1");
+derivative_test!(other_variable, "y", "0");
+derivative_test!(linear, "3 * x + 5", "3");
+derivative_test!(constant_factor, "a * x", "a");
+derivative_test!(power_rule, "x ^ 3", "3 * x ^ 2");
+derivative_test!(square, "x ^ 2", "2 * x");
+derivative_test!(
+    product_rule,
+    "x ^ 2 * sin(x)",
+    "2 * x * sin(x) + x ^ 2 * cos(x)"
+);
+derivative_test!(sine, "sin(x)", "cos(x)");
+derivative_test!(cosine, "cos(x)", "-sin(x)");
+derivative_test!(tangent, "tan(x)", "1 / cos(x) ^ 2");
+derivative_test!(exponential, "exp(x)", "exp(x)");
+derivative_test!(logarithm, "ln(x)", "1 / x");
+derivative_test!(square_root, "sqrt(x)", "1 / (2 * sqrt(x))");
+derivative_test!(chain_rule, "exp(2 * x)", "exp(2 * x) * 2");
+derivative_test!(nested_chain_rule, "sin(x ^ 2)", "cos(x ^ 2) * (2 * x)");
+derivative_test!(constant_base, "2 ^ x", "2 ^ x * ln(2)");
+derivative_test!(variable_exponent, "x ^ x", "x ^ x * (ln(x) + x / x)");
+derivative_test!(quotient_rule, "sin(x) / x", "(cos(x) * x - sin(x)) / x ^ 2");
+derivative_test!(constant_denominator, "x ^ 3 / 3", "3 * x ^ 2 / 3");
+derivative_test!(percent, "3 * x%", "0.03");
+derivative_test!(percent_of_power, "(x ^ 2)%", "(2 * x)%");
+derivative_test!(
+    conditional,
+    "if x > 0 then x ^ 2 else 3 * x",
+    "if x > 0 then 2 * x else 3"
+);
+derivative_test!(constant_call, "abs(y) * x", "abs(y)");
+derivative_test!(constant_call_of_two_arguments, "max(1, y) + x", "1");
+derivative_test!(constant_comparison, "(y > 1) * x", "y > 1");
+derivative_test!(constant_factorial, "3! * x", "6");
+derivative_test!(constant_not, "x - !y", "1");
+
+/// Inputs whose derivative with respect to `x` is checked against a central difference.
+const NUMERIC: &[&str] = &[
+    "x ^ 2 * sin(x)",
+    "1 / x",
+    "-x ^ 3 + 2 * x - 7",
+    "tan(x) * cos(x)",
+    "exp(-x) / (1 + x)",
+    "ln(x ^ 2 + 1)",
+    "sqrt(1 + x * x)",
+    "x ^ x",
+    "(2 * x + 1) ^ 0.5",
+    "if x < 1 then -x else x ^ 2",
+    "(x ^ 3 - x)% * 5",
+];
+
+#[test]
+fn derivatives_match_central_differences() {
+    let h = 1e-6;
+    let mut evaluator = Evaluator::new(FloatBuiltins);
+    for input in NUMERIC {
+        let derivative = diff(&expression(input), "x").unwrap();
+        for x in [0.5, 1.3, 2.0, 3.7] {
+            let mut at = |expression: Expression<f64>, x: f64| {
+                evaluator.set_variable(String::from("x"), x).unwrap();
+                evaluator.eval_expression(expression).unwrap()
+            };
+            let exact = at(derivative.clone(), x);
+            let approximate =
+                (at(expression(input), x + h) - at(expression(input), x - h)) / (2.0 * h);
+            assert!(
+                (exact - approximate).abs() <= 1e-4 * exact.abs().max(1.0),
+                "derivative of {input} at {x} is {exact}, expected about {approximate}",
+            );
+        }
+    }
+}
+
+#[test]
+fn unsupported_functions() {
+    assert!(matches!(
+        diff(&expression("f(x)"), "x"),
+        Err(DerivativeError::UnsupportedFunction(name, _)) if name == "f"
+    ));
+    assert!(matches!(
+        diff(&expression("2 * max(x, 1)"), "x"),
+        Err(DerivativeError::UnsupportedFunction(name, _)) if name == "max"
+    ));
+}
+
+#[test]
+fn unsupported_operators() {
+    assert_eq!(
+        diff(&expression("x + (x > 1)"), "x").unwrap_err(),
+        DerivativeError::UnsupportedOperator(Operator::Greater, Span::new(4, 11))
+    );
+    assert!(matches!(
+        diff(&expression("!x"), "x"),
+        Err(DerivativeError::UnsupportedOperator(Operator::Not, _))
+    ));
+    assert_eq!(
+        diff(&expression("2 * x!"), "x").unwrap_err(),
+        DerivativeError::UnsupportedFactorial(String::from("x"), Span::new(4, 6))
+    );
+    let error = diff(&expression("(t + 1)!"), "t").unwrap_err();
+    assert_eq!(error.span(), Span::new(0, 8));
+    assert_eq!(
+        error.to_string(),
+        "cannot differentiate a factorial with respect to `t`"
+    );
+    assert!(matches!(
+        diff(&expression("x mod 2"), "x"),
+        Err(DerivativeError::UnsupportedOperator(Operator::Rem, _))
+    ));
+}
+
+#[test]
+fn names_other_than_the_variable_are_constants() {
+    let derivative = diff(&expression("a * x ^ 2 + b * x + c"), "x").unwrap();
+    let mut evaluator = Evaluator::new(FloatBuiltins);
+    let values = HashMap::from([("a", 2.0), ("b", -3.0), ("c", 10.0), ("x", 4.0)]);
+    for (name, value) in values {
+        evaluator.set_variable(name.to_owned(), value).unwrap();
+    }
+    assert_eq!(evaluator.eval_expression(derivative).unwrap(), 13.0);
+}
@@ -0,0 +1,381 @@
+use std::collections::HashMap;
+use std::fmt::{self, Debug};
+
+use crate::evaluator::error::EvaluatorError;
+use crate::evaluator::{Evaluator, LAST_RESULT};
+use crate::lexer::token::Operator;
+use crate::numeric::{Arity, BuiltinFn, NumericValue, from_bool};
+use crate::optimizer::optimize;
+use crate::parser::Parser;
+use crate::parser::ast::{Expression, ExpressionKind, Statement, UnaryOp};
+use crate::span::Span;
+
+/// An expression parsed and resolved once, to be evaluated many times for different values of
+/// its parameters.
+///
+/// Parameters are resolved to slot indices, and everything else is fixed when compiling:
+/// constants, the variables and results of the evaluator it was compiled by, and user functions,
+/// which are inlined. The resolved expression is simplified with [`optimize`].
+///
+/// Like in the evaluator, the arguments of an inlined user function are evaluated once, before
+/// its body, whether the body uses them or not. Those that are neither constants nor parameters
+/// are kept in local slots for that.
+///
+/// Evaluating allocates nothing beyond what the arithmetic of `N` itself needs, except for the
+/// local slots and for calls to builtins with more than three arguments.
+pub struct CompiledExpr<'a, N: NumericValue, F: BuiltinFn<N>> {
+    root: Node<N>,
+    parameters: Vec<String>,
+    /// The number of local slots.
+    locals: usize,
+    builtins: &'a F,
+}
+
+#[derive(Debug, Clone)]
+enum Node<N: NumericValue> {
+    Constant(N),
+    /// The value of the parameter with this index.
+    Slot(usize),
+    /// The value of the local slot with this index.
+    Local(usize),
+    /// Evaluates the arguments of an inlined user function into their local slots, in order,
+    /// and then the body.
+    Bind(Vec<(usize, Node<N>)>, Box<Node<N>>),
+    Unary(UnaryOp<N>, Box<Node<N>>, Span),
+    Binary(Box<Node<N>>, Operator, Box<Node<N>>, Span),
+    Conditional(Box<Node<N>>, Box<Node<N>>, Box<Node<N>>),
+    Call(String, Vec<Node<N>>, Span),
+}
+
+impl<N: NumericValue + Debug, F: BuiltinFn<N>> Debug for CompiledExpr<'_, N, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CompiledExpr")
+            .field("root", &self.root)
+            .field("parameters", &self.parameters)
+            .field("locals", &self.locals)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<N: NumericValue, F: BuiltinFn<N>> CompiledExpr<'_, N, F> {
+    /// The parameter names, in the order their values are passed to [`Self::eval`].
+    pub fn parameters(&self) -> &[String] {
+        &self.parameters
+    }
+
+    /// Evaluates the expression with `values[i]` as the value of the `i`-th parameter.
+    ///
+    /// Fails with [`EvaluatorError::ParameterCountMismatch`] if the number of values differs from
+    /// the number of parameters.
+    pub fn eval(&self, values: &[N]) -> Result<N, EvaluatorError> {
+        if values.len() != self.parameters.len() {
+            return Err(EvaluatorError::ParameterCountMismatch(
+                self.parameters.len(),
+                values.len(),
+            ));
+        }
+        let mut locals = vec![N::zero(); self.locals];
+        self.eval_node(&self.root, values, &mut locals)
+    }
+
+    fn eval_node(
+        &self,
+        node: &Node<N>,
+        values: &[N],
+        locals: &mut [N],
+    ) -> Result<N, EvaluatorError> {
+        match node {
+            Node::Constant(value) => Ok(value.clone()),
+            Node::Slot(index) => Ok(values[*index].clone()),
+            Node::Local(index) => Ok(locals[*index].clone()),
+            Node::Bind(arguments, body) => {
+                for (index, argument) in arguments {
+                    locals[*index] = self.eval_node(argument, values, locals)?;
+                }
+                self.eval_node(body, values, locals)
+            }
+            Node::Unary(op, operand, span) => op
+                .apply(self.eval_node(operand, values, locals)?)
+                .map_err(|message| EvaluatorError::OperationFailed(message, *span)),
+            Node::Binary(left, operator @ (Operator::And | Operator::Or), right, _) => {
+                let left = self.eval_node(left, values, locals)? != N::zero();
+                if left == (*operator == Operator::Or) {
+                    return Ok(from_bool(left));
+                }
+                Ok(from_bool(
+                    self.eval_node(right, values, locals)? != N::zero(),
+                ))
+            }
+            Node::Binary(left, operator, right, span) => operator
+                .apply(
+                    self.eval_node(left, values, locals)?,
+                    self.eval_node(right, values, locals)?,
+                )
+                .map_err(|message| EvaluatorError::OperationFailed(message, *span)),
+            Node::Conditional(condition, then_branch, else_branch) => {
+                if self.eval_node(condition, values, locals)? != N::zero() {
+                    self.eval_node(then_branch, values, locals)
+                } else {
+                    self.eval_node(else_branch, values, locals)
+                }
+            }
+            Node::Call(name, arguments, span) => {
+                let mut argument = |i: usize| self.eval_node(&arguments[i], values, locals);
+                // arguments of the common arities go on the stack
+                let result = match arguments.len() {
+                    1 => self.builtins.call(name, &[argument(0)?]),
+                    2 => self.builtins.call(name, &[argument(0)?, argument(1)?]),
+                    3 => {
+                        let arguments = [argument(0)?, argument(1)?, argument(2)?];
+                        self.builtins.call(name, &arguments)
+                    }
+                    count => {
+                        let arguments = (0..count).map(argument).collect::<Result<Vec<_>, _>>()?;
+                        self.builtins.call(name, &arguments)
+                    }
+                };
+                result.map_err(|message| EvaluatorError::OperationFailed(message, *span))
+            }
+        }
+    }
+}
+
+impl<N: NumericValue, F: BuiltinFn<N>> Evaluator<N, F> {
+    /// Compiles the single expression `input` with the given parameters, see [`CompiledExpr`].
+    pub fn compile(
+        &self,
+        input: &str,
+        parameters: &[&str],
+    ) -> Result<CompiledExpr<'_, N, F>, EvaluatorError> {
+        if let Some(constant) = parameters
+            .iter()
+            .find(|name| self.constants().contains_key(**name))
+        {
+            return Err(EvaluatorError::InvalidAssignment(
+                constant.to_string(),
+                Span::default(),
+            ));
+        }
+        let mut statements = Parser::with_options(input, self.parser_options())
+            .parse_program()
+            .map_err(EvaluatorError::ParserError)?;
+        statements.retain(|statement| *statement != Statement::Empty);
+        let expression = match (statements.pop(), statements.is_empty()) {
+            (Some(Statement::Expression(expression)), true) => expression,
+            _ => return Err(EvaluatorError::NotAnExpression(Span::new(0, input.len()))),
+        };
+        let mut resolver = Resolver {
+            evaluator: self,
+            parameters,
+            inlining: Vec::new(),
+            locals: 0,
+        };
+        let resolved = resolver.resolve(expression, &HashMap::new())?;
+        Ok(CompiledExpr {
+            root: lower(optimize(resolved), parameters),
+            parameters: parameters.iter().map(|name| name.to_string()).collect(),
+            locals: resolver.locals,
+            builtins: self.builtins(),
+        })
+    }
+}
+
+/// Replaces everything but the parameters in an expression by what it stands for in the
+/// evaluator.
+struct Resolver<'a, N: NumericValue, F: BuiltinFn<N>> {
+    evaluator: &'a Evaluator<N, F>,
+    parameters: &'a [&'a str],
+    /// The user functions being inlined, outermost first.
+    inlining: Vec<String>,
+    /// The number of local slots handed out.
+    locals: usize,
+}
+
+/// The name of the call that binds the arguments of an inlined user function to local slots in
+/// a resolved expression, `#bind(#0, a, #1, b, body)`. Names starting with `#` cannot be written
+/// in the input, as `#` starts a comment.
+const BIND: &str = "#bind";
+
+/// The name of the variable standing for the local slot `index` in a resolved expression.
+fn local_name(index: usize) -> String {
+    format!("#{index}")
+}
+
+impl<N: NumericValue, F: BuiltinFn<N>> Resolver<'_, N, F> {
+    /// Resolves `expression`, `scope` holding the resolved arguments of the user function whose
+    /// body it is.
+    fn resolve(
+        &mut self,
+        expression: Expression<N>,
+        scope: &HashMap<String, Expression<N>>,
+    ) -> Result<Expression<N>, EvaluatorError> {
+        let span = expression.span;
+        let number = |value: N| Ok(Expression::new(ExpressionKind::Number(value), span));
+        let kind = match expression.kind {
+            ExpressionKind::Variable(name) => {
+                if let Some(argument) = scope.get(&name) {
+                    return Ok(argument.clone());
+                }
+                if let Some(constant) = self.evaluator.constants().get(&name) {
+                    return number(constant.clone());
+                }
+                if self.parameters.contains(&name.as_str()) {
+                    ExpressionKind::Variable(name)
+                } else if let Some(value) = self.evaluator.variables().get(&name) {
+                    return number(value.clone());
+                } else if let Some(result) = self.evaluator.last_result()
+                    && name == LAST_RESULT
+                {
+                    return number(result.clone());
+                } else {
+                    return Err(EvaluatorError::UndefinedVariable(name, span));
+                }
+            }
+            ExpressionKind::ResultRef(index) => {
+                return match self.evaluator.result(index) {
+                    Some(result) => number(result.clone()),
+                    None => Err(EvaluatorError::UnknownResult(index, span)),
+                };
+            }
+            ExpressionKind::Call(name, arguments)
+                if self.evaluator.functions().contains_key(&name) =>
+            {
+                return self.inline(name, arguments, scope, span);
+            }
+            ExpressionKind::Call(name, arguments) => {
+                let Some(arity) = self.evaluator.builtins().arity(&name) else {
+                    return Err(EvaluatorError::UnknownFunction(name, span));
+                };
+                if !arity.accepts(arguments.len()) {
+                    return Err(EvaluatorError::ArityMismatch(
+                        name,
+                        arity,
+                        arguments.len(),
+                        span,
+                    ));
+                }
+                let arguments = arguments
+                    .into_iter()
+                    .map(|argument| self.resolve(argument, scope))
+                    .collect::<Result<_, _>>()?;
+                ExpressionKind::Call(name, arguments)
+            }
+            ExpressionKind::Number(value) => ExpressionKind::Number(value),
+            ExpressionKind::Unary(op, operand) => {
+                ExpressionKind::Unary(op, Box::new(self.resolve(*operand, scope)?))
+            }
+            ExpressionKind::Binary(left, op, right) => ExpressionKind::Binary(
+                Box::new(self.resolve(*left, scope)?),
+                op,
+                Box::new(self.resolve(*right, scope)?),
+            ),
+            ExpressionKind::Conditional(condition, then_branch, else_branch) => {
+                ExpressionKind::Conditional(
+                    Box::new(self.resolve(*condition, scope)?),
+                    Box::new(self.resolve(*then_branch, scope)?),
+                    Box::new(self.resolve(*else_branch, scope)?),
+                )
+            }
+        };
+        Ok(Expression::new(kind, span))
+    }
+
+    /// Replaces a call of the user function `name` by its body, with the parameters replaced by
+    /// the arguments. Arguments that are neither constants nor parameters are bound to local
+    /// slots, so that they are evaluated exactly once, as by the evaluator.
+    fn inline(
+        &mut self,
+        name: String,
+        arguments: Vec<Expression<N>>,
+        scope: &HashMap<String, Expression<N>>,
+        span: Span,
+    ) -> Result<Expression<N>, EvaluatorError> {
+        let function = &self.evaluator.functions()[&name];
+        if function.parameters().len() != arguments.len() {
+            return Err(EvaluatorError::ArityMismatch(
+                name,
+                Arity::exact(function.parameters().len()),
+                arguments.len(),
+                span,
+            ));
+        }
+        // a recursive function would be inlined forever
+        if self.inlining.contains(&name) {
+            return Err(EvaluatorError::RecursiveFunction(name, span));
+        }
+        let mut body_scope = HashMap::with_capacity(arguments.len());
+        let mut bindings = Vec::new();
+        for (parameter, argument) in function.parameters().iter().zip(arguments) {
+            let argument = optimize(self.resolve(argument, scope)?);
+            let argument = match argument.kind {
+                // cannot fail and cost nothing to repeat
+                ExpressionKind::Number(_) | ExpressionKind::Variable(_) => argument,
+                _ => {
+                    let local = ExpressionKind::Variable(local_name(self.locals));
+                    let local = Expression::new(local, argument.span);
+                    self.locals += 1;
+                    bindings.extend([local.clone(), argument]);
+                    local
+                }
+            };
+            body_scope.insert(parameter.clone(), argument);
+        }
+        let body = function.body().clone();
+        self.inlining.push(name);
+        let inlined = self.resolve(body, &body_scope);
+        self.inlining.pop();
+        if bindings.is_empty() {
+            return inlined;
+        }
+        bindings.push(inlined?);
+        Ok(Expression::new(
+            ExpressionKind::Call(BIND.to_owned(), bindings),
+            span,
+        ))
+    }
+}
+
+/// Turns a resolved expression into nodes, with the variables left in it being parameters.
+fn lower<N: NumericValue>(expression: Expression<N>, parameters: &[&str]) -> Node<N> {
+    let lower = |expression: Box<Expression<N>>| Box::new(lower(*expression, parameters));
+    match expression.kind {
+        ExpressionKind::Number(value) => Node::Constant(value),
+        ExpressionKind::Variable(name) => match name.strip_prefix('#') {
+            Some(index) => Node::Local(index.parse().expect("locals are numbered")),
+            None => Node::Slot(
+                parameters
+                    .iter()
+                    .position(|parameter| *parameter == name)
+                    .expect("only parameters and locals are left unresolved"),
+            ),
+        },
+        ExpressionKind::ResultRef(_) => unreachable!("results are resolved"),
+        ExpressionKind::Unary(op, operand) => Node::Unary(op, lower(operand), expression.span),
+        ExpressionKind::Binary(left, op, right) => {
+            Node::Binary(lower(left), op, lower(right), expression.span)
+        }
+        ExpressionKind::Conditional(condition, then_branch, else_branch) => {
+            Node::Conditional(lower(condition), lower(then_branch), lower(else_branch))
+        }
+        ExpressionKind::Call(name, mut arguments) if name == BIND => {
+            let body = arguments.pop().expect("a binding has a body");
+            let mut arguments = arguments.into_iter();
+            let mut bindings = Vec::new();
+            while let (Some(local), Some(argument)) = (arguments.next(), arguments.next()) {
+                let Node::Local(index) = self::lower(local, parameters) else {
+                    unreachable!("arguments are bound to locals");
+                };
+                bindings.push((index, self::lower(argument, parameters)));
+            }
+            Node::Bind(bindings, Box::new(self::lower(body, parameters)))
+        }
+        ExpressionKind::Call(name, arguments) => Node::Call(
+            name,
+            arguments
+                .into_iter()
+                .map(|argument| self::lower(argument, parameters))
+                .collect(),
+            expression.span,
+        ),
+    }
+}
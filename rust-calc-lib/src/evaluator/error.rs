@@ -1,12 +1,90 @@
-use crate::numeric::NumericValue;
+use std::error::Error;
+use std::fmt::{self, Display};
+
+use crate::numeric::Arity;
 use crate::parser::error::ParserError;
+use crate::span::Span;
 
 #[derive(Debug)]
-pub enum EvaluatorError<N: NumericValue> {
-    ParserError(ParserError<N>),
+pub enum EvaluatorError {
+    ParserError(ParserError),
     UnexpectedError,
-    OperationFailed(String),
-    UndefinedVariable(String),
-    UnknownFunction(String),
-    InvalidAssignment(String),
+    OperationFailed(String, Span),
+    UndefinedVariable(String, Span),
+    UnknownFunction(String, Span),
+    InvalidAssignment(String, Span),
+    /// A function was called with a number of arguments its arity does not accept.
+    ArityMismatch(String, Arity, usize, Span),
+    /// Nested user function calls went deeper than the limit set with
+    /// [`crate::evaluator::Evaluator::set_max_call_depth`]: function name and limit.
+    RecursionLimitExceeded(String, usize, Span),
+    /// A `$n` reference to a result that was never recorded or no longer is.
+    UnknownResult(usize, Span),
+    /// The input to compile is not a single expression.
+    NotAnExpression(Span),
+    /// A recursive user function was called in an expression to compile, which cannot be inlined.
+    RecursiveFunction(String, Span),
+    /// A compiled expression was evaluated with a number of values other than its number of
+    /// parameters: expected and found count.
+    ParameterCountMismatch(usize, usize),
+}
+
+impl EvaluatorError {
+    /// Returns the part of the input the error originates from, if it is known.
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            EvaluatorError::ParserError(parser_error) => Some(parser_error.span()),
+            EvaluatorError::UnexpectedError | EvaluatorError::ParameterCountMismatch(..) => None,
+            EvaluatorError::OperationFailed(_, span)
+            | EvaluatorError::UndefinedVariable(_, span)
+            | EvaluatorError::UnknownFunction(_, span)
+            | EvaluatorError::InvalidAssignment(_, span)
+            | EvaluatorError::ArityMismatch(_, _, _, span)
+            | EvaluatorError::RecursionLimitExceeded(_, _, span)
+            | EvaluatorError::UnknownResult(_, span)
+            | EvaluatorError::NotAnExpression(span)
+            | EvaluatorError::RecursiveFunction(_, span) => Some(*span),
+        }
+    }
+}
+
+impl Display for EvaluatorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EvaluatorError::ParserError(_) => f.write_str("syntax error"),
+            EvaluatorError::UnexpectedError => f.write_str("unexpected error"),
+            EvaluatorError::OperationFailed(message, _) => f.write_str(message),
+            EvaluatorError::UndefinedVariable(name, _) => write!(f, "undefined variable `{name}`"),
+            EvaluatorError::UnknownFunction(name, _) => write!(f, "unknown function `{name}`"),
+            EvaluatorError::InvalidAssignment(name, _) => {
+                write!(f, "cannot assign to constant `{name}`")
+            }
+            EvaluatorError::ArityMismatch(name, arity, found, _) => write!(
+                f,
+                "function `{name}` takes {arity} argument(s) but {found} were given"
+            ),
+            EvaluatorError::RecursionLimitExceeded(name, limit, _) => write!(
+                f,
+                "recursion limit exceeded in `{name}` (more than {limit} nested calls)"
+            ),
+            EvaluatorError::UnknownResult(number, _) => write!(f, "there is no result `${number}`"),
+            EvaluatorError::NotAnExpression(_) => f.write_str("expected a single expression"),
+            EvaluatorError::RecursiveFunction(name, _) => {
+                write!(f, "recursive function `{name}` cannot be compiled")
+            }
+            EvaluatorError::ParameterCountMismatch(expected, found) => write!(
+                f,
+                "expected {expected} parameter value(s) but {found} were given"
+            ),
+        }
+    }
+}
+
+impl Error for EvaluatorError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            EvaluatorError::ParserError(parser_error) => Some(parser_error),
+            _ => None,
+        }
+    }
 }
@@ -1,16 +1,65 @@
+pub mod compiled;
 pub mod error;
+#[cfg(test)]
+mod tests;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 
 use crate::evaluator::error::EvaluatorError;
-use crate::numeric::{BuiltinFn, NumericValue};
-use crate::parser::Parser;
-use crate::parser::ast::{Expression, Statement};
+use crate::lexer::token::Operator;
+use crate::numeric::{Arity, BuiltinFn, NumericValue, from_bool};
+use crate::optimizer::optimize;
+use crate::parser::ast::{Expression, ExpressionKind, Statement};
+use crate::parser::{Parser, ParserOptions};
+use crate::span::Span;
+
+/// The number of nested user function calls after which an [`Evaluator`] aborts evaluation by
+/// default. Deep enough for ordinary recursion such as `fact(200)`, and shallow enough for the
+/// stack of a main thread even in unoptimized builds.
+pub const DEFAULT_MAX_CALL_DEPTH: usize = 256;
+
+/// The number of expression results an [`Evaluator`] keeps for `$n` references by default.
+pub const DEFAULT_HISTORY_LIMIT: usize = 1000;
+
+/// The name that refers to the most recent expression result.
+pub const LAST_RESULT: &str = "ans";
+
+#[derive(Debug, Clone)]
+pub struct UserFunction<N: NumericValue> {
+    parameters: Vec<String>,
+    body: Expression<N>,
+    /// The body as the user wrote it.
+    source: String,
+}
+
+impl<N: NumericValue> UserFunction<N> {
+    pub fn parameters(&self) -> &[String] {
+        &self.parameters
+    }
+
+    pub fn body(&self) -> &Expression<N> {
+        &self.body
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+}
 
 pub struct Evaluator<N: NumericValue, F: BuiltinFn<N>> {
     variables: HashMap<String, N>,
     constants: HashMap<String, N>,
+    functions: HashMap<String, UserFunction<N>>,
+    /// Parameter bindings of the user function calls being evaluated, innermost last.
+    scopes: Vec<HashMap<String, N>>,
     builtins: F,
+    /// The most recent expression results, oldest first.
+    history: VecDeque<N>,
+    history_limit: usize,
+    max_call_depth: usize,
+    /// The number of expression results recorded so far, including those no longer kept.
+    result_count: usize,
+    parser_options: ParserOptions,
 }
 
 impl<N: NumericValue, F: BuiltinFn<N>> Evaluator<N, F> {
@@ -18,61 +67,350 @@ impl<N: NumericValue, F: BuiltinFn<N>> Evaluator<N, F> {
         Self {
             variables: HashMap::new(),
             constants: builtins.constants(),
+            functions: HashMap::new(),
+            scopes: Vec::new(),
             builtins,
+            history: VecDeque::new(),
+            history_limit: DEFAULT_HISTORY_LIMIT,
+            max_call_depth: DEFAULT_MAX_CALL_DEPTH,
+            result_count: 0,
+            parser_options: ParserOptions::default(),
+        }
+    }
+
+    /// The variables assigned so far, without constants.
+    pub fn variables(&self) -> &HashMap<String, N> {
+        &self.variables
+    }
+
+    /// Returns the value of the variable or constant `name`.
+    pub fn variable(&self, name: &str) -> Option<&N> {
+        self.constants
+            .get(name)
+            .or_else(|| self.variables.get(name))
+    }
+
+    /// Assigns `value` to the variable `name`, like `name = value;` would.
+    pub fn set_variable(&mut self, name: String, value: N) -> Result<(), EvaluatorError> {
+        if self.constants.contains_key(&name) {
+            return Err(EvaluatorError::InvalidAssignment(name, Span::default()));
+        }
+        self.variables.insert(name, value);
+        Ok(())
+    }
+
+    /// Removes the variable `name`, returning its value if it was defined.
+    pub fn remove_variable(&mut self, name: &str) -> Option<N> {
+        self.variables.remove(name)
+    }
+
+    pub fn constants(&self) -> &HashMap<String, N> {
+        &self.constants
+    }
+
+    /// The user defined functions, by name.
+    pub fn functions(&self) -> &HashMap<String, UserFunction<N>> {
+        &self.functions
+    }
+
+    pub fn builtins(&self) -> &F {
+        &self.builtins
+    }
+
+    /// Returns the `number`-th expression result, counting from one, if it is still kept.
+    pub fn result(&self, number: usize) -> Option<&N> {
+        let first_kept = self.result_count - self.history.len() + 1;
+        number
+            .checked_sub(first_kept)
+            .and_then(|index| self.history.get(index))
+    }
+
+    /// The value of `ans`, the most recent expression result.
+    pub fn last_result(&self) -> Option<&N> {
+        self.history.back()
+    }
+
+    /// How many expression results are kept for `$n` references.
+    pub fn history_limit(&self) -> usize {
+        self.history_limit
+    }
+
+    /// Sets how many expression results are kept for `$n` references, dropping the oldest ones
+    /// beyond the new limit. Result numbers keep counting up regardless.
+    pub fn set_history_limit(&mut self, limit: usize) {
+        self.history_limit = limit;
+        while self.history.len() > limit {
+            self.history.pop_front();
         }
     }
 
-    pub fn parse(&mut self, input: &str) -> Result<Option<N>, EvaluatorError<N>> {
-        let mut parser = Parser::new(input);
+    /// How many user function calls may be nested before evaluation fails with
+    /// [`EvaluatorError::RecursionLimitExceeded`].
+    pub fn max_call_depth(&self) -> usize {
+        self.max_call_depth
+    }
+
+    /// Sets the nesting limit of user function calls, which may be raised when evaluating on a
+    /// thread with a larger stack than [`DEFAULT_MAX_CALL_DEPTH`] is meant for, or lowered for a
+    /// smaller one.
+    pub fn set_max_call_depth(&mut self, depth: usize) {
+        self.max_call_depth = depth;
+    }
+
+    /// How input is read by [`Self::parse`], [`Self::evaluate_each`] and [`Self::compile`].
+    pub fn parser_options(&self) -> ParserOptions {
+        self.parser_options
+    }
+
+    pub fn set_parser_options(&mut self, options: ParserOptions) {
+        self.parser_options = options;
+    }
+
+    /// Records an expression result, returning its number.
+    fn record_result(&mut self, result: N) -> usize {
+        self.result_count += 1;
+        self.history.push_back(result);
+        if self.history.len() > self.history_limit {
+            self.history.pop_front();
+        }
+        self.result_count
+    }
+
+    /// Forgets all variables, user functions and results, leaving only the constants and
+    /// builtins.
+    pub fn reset(&mut self) {
+        self.variables.clear();
+        self.functions.clear();
+        self.scopes.clear();
+        self.history.clear();
+        self.result_count = 0;
+    }
+
+    pub fn parse(&mut self, input: &str) -> Result<Option<N>, EvaluatorError> {
+        let mut parser = Parser::with_options(input, self.parser_options);
         let mut res = Err(EvaluatorError::UnexpectedError);
         for statement in parser
             .parse_program()
             .map_err(EvaluatorError::ParserError)?
         {
-            res = self.eval_statement(statement)
+            res = self.eval_statement(statement, input)
         }
         res
     }
 
-    fn eval_statement(&mut self, statement: Statement<N>) -> Result<Option<N>, EvaluatorError<N>> {
+    /// Evaluates every statement of `input`, passing the result number and value of each
+    /// expression statement to `on_result` as soon as it is known. Statements before a failing
+    /// one keep their effects.
+    pub fn evaluate_each(
+        &mut self,
+        input: &str,
+        mut on_result: impl FnMut(usize, N),
+    ) -> Result<(), EvaluatorError> {
+        let mut parser = Parser::with_options(input, self.parser_options);
+        for statement in parser
+            .parse_program()
+            .map_err(EvaluatorError::ParserError)?
+        {
+            if let Some(result) = self.eval_statement(statement, input)? {
+                on_result(self.result_count, result);
+            }
+        }
+        Ok(())
+    }
+
+    /// Evaluates every statement of `input` like [`Self::evaluate_each`], but on its own each: the
+    /// error of a statement that fails to parse or evaluate is passed to `on_error`, and
+    /// evaluation goes on with the next statement.
+    pub fn evaluate_all(
+        &mut self,
+        input: &str,
+        mut on_result: impl FnMut(usize, N),
+        mut on_error: impl FnMut(EvaluatorError),
+    ) {
+        let mut parser = Parser::with_options(input, self.parser_options);
+        while let Some(statement) = parser.next_statement() {
+            match statement
+                .map_err(EvaluatorError::ParserError)
+                .and_then(|statement| self.eval_statement(statement, input))
+            {
+                Ok(Some(result)) => on_result(self.result_count, result),
+                Ok(None) => {}
+                Err(e) => on_error(e),
+            }
+        }
+    }
+
+    /// Evaluates a statement parsed from `input`.
+    fn eval_statement(
+        &mut self,
+        statement: Statement<N>,
+        input: &str,
+    ) -> Result<Option<N>, EvaluatorError> {
         match statement {
             Statement::Assignment(var_name, expression) => {
-                if self.constants.contains_key(&var_name) {
-                    return Err(EvaluatorError::InvalidAssignment(var_name));
+                if self.constants.contains_key(&var_name.node) {
+                    return Err(EvaluatorError::InvalidAssignment(
+                        var_name.node,
+                        var_name.span,
+                    ));
                 }
                 let expr_res = self.eval_expression(expression)?;
-                self.variables.insert(var_name, expr_res);
+                self.variables.insert(var_name.node, expr_res);
+                Ok(None)
+            }
+            Statement::FunctionDefinition(func_name, parameters, body) => {
+                let source = input[body.span.start..body.span.end].to_owned();
+                // bodies are evaluated on every call, so they are simplified once up front
+                self.functions.insert(
+                    func_name.node,
+                    UserFunction {
+                        parameters,
+                        body: optimize(body),
+                        source,
+                    },
+                );
                 Ok(None)
             }
-            Statement::Expression(expression) => self.eval_expression(expression).map(Some),
+            Statement::Expression(expression) => {
+                let result = self.eval_expression(expression)?;
+                self.record_result(result.clone());
+                Ok(Some(result))
+            }
             Statement::Empty => Ok(None),
         }
     }
 
-    fn eval_expression(&mut self, expression: Expression<N>) -> Result<N, EvaluatorError<N>> {
-        match expression {
-            Expression::Number(n) => Ok(n),
-            Expression::Variable(var) => self
-                .constants
-                .get(&var)
-                .or_else(|| self.variables.get(&var))
-                .ok_or(EvaluatorError::UndefinedVariable(var))
-                .cloned(),
-            Expression::Unary(unary_op, expression) => {
-                Ok(unary_op.apply(self.eval_expression(*expression)?))
+    // Every call of a user function recurses through this method, so the work of the arms is
+    // done in separate methods to keep its stack frame small and allow deep recursion.
+    pub(crate) fn eval_expression(
+        &mut self,
+        expression: Expression<N>,
+    ) -> Result<N, EvaluatorError> {
+        let span = expression.span;
+        match expression.kind {
+            ExpressionKind::Number(n) => Ok(n),
+            ExpressionKind::ResultRef(number) => self
+                .result(number)
+                .cloned()
+                .ok_or(EvaluatorError::UnknownResult(number, span)),
+            ExpressionKind::Variable(var) => self.eval_variable(var, span),
+            ExpressionKind::Unary(unary_op, expression) => {
+                let operand = self.eval_expression(*expression)?;
+                unary_op
+                    .apply(operand)
+                    .map_err(|message| EvaluatorError::OperationFailed(message, span))
+            }
+            ExpressionKind::Binary(left, operator, right) => {
+                self.eval_binary(*left, operator, *right, span)
+            }
+            ExpressionKind::Conditional(condition, then_branch, else_branch) => {
+                if self.eval_expression(*condition)? != N::zero() {
+                    self.eval_expression(*then_branch)
+                } else {
+                    self.eval_expression(*else_branch)
+                }
+            }
+            ExpressionKind::Call(func_name, expressions)
+                if self.functions.contains_key(&func_name) =>
+            {
+                self.call_user_function(func_name, expressions, span)
             }
-            Expression::Binary(expression, operator, expression1) => operator
-                .apply(
-                    self.eval_expression(*expression)?,
-                    self.eval_expression(*expression1)?,
-                )
-                .map_err(EvaluatorError::OperationFailed),
-            Expression::Call(func_name, expression) => {
-                let argument = self.eval_expression(*expression)?;
-                self.builtins
-                    .call(&func_name, argument)
-                    .ok_or_else(|| EvaluatorError::UnknownFunction(func_name))
+            ExpressionKind::Call(func_name, expressions) => {
+                self.call_builtin(func_name, expressions, span)
+            }
+        }
+    }
+
+    fn eval_variable(&self, var: String, span: Span) -> Result<N, EvaluatorError> {
+        self.scopes
+            .last()
+            .and_then(|scope| scope.get(&var))
+            .or_else(|| self.constants.get(&var))
+            .or_else(|| self.variables.get(&var))
+            .or_else(|| (var == LAST_RESULT).then(|| self.last_result()).flatten())
+            .cloned()
+            .ok_or(EvaluatorError::UndefinedVariable(var, span))
+    }
+
+    fn eval_binary(
+        &mut self,
+        left: Expression<N>,
+        operator: Operator,
+        right: Expression<N>,
+        span: Span,
+    ) -> Result<N, EvaluatorError> {
+        let left = self.eval_expression(left)?;
+        if matches!(operator, Operator::And | Operator::Or) {
+            // short-circuit, the right operand is only evaluated if it decides the result
+            let left = left != N::zero();
+            if left == (operator == Operator::Or) {
+                return Ok(from_bool(left));
             }
+            let right = self.eval_expression(right)? != N::zero();
+            return Ok(from_bool(right));
+        }
+        let right = self.eval_expression(right)?;
+        operator
+            .apply(left, right)
+            .map_err(|message| EvaluatorError::OperationFailed(message, span))
+    }
+
+    fn call_builtin(
+        &mut self,
+        func_name: String,
+        expressions: Vec<Expression<N>>,
+        span: Span,
+    ) -> Result<N, EvaluatorError> {
+        let Some(arity) = self.builtins.arity(&func_name) else {
+            return Err(EvaluatorError::UnknownFunction(func_name, span));
+        };
+        if !arity.accepts(expressions.len()) {
+            return Err(EvaluatorError::ArityMismatch(
+                func_name,
+                arity,
+                expressions.len(),
+                span,
+            ));
+        }
+        let mut arguments = Vec::with_capacity(expressions.len());
+        for expression in expressions {
+            arguments.push(self.eval_expression(expression)?);
+        }
+        self.builtins
+            .call(&func_name, &arguments)
+            .map_err(|message| EvaluatorError::OperationFailed(message, span))
+    }
+
+    fn call_user_function(
+        &mut self,
+        func_name: String,
+        expressions: Vec<Expression<N>>,
+        span: Span,
+    ) -> Result<N, EvaluatorError> {
+        let function = &self.functions[&func_name];
+        let (parameters, body) = (function.parameters.clone(), function.body.clone());
+        if parameters.len() != expressions.len() {
+            return Err(EvaluatorError::ArityMismatch(
+                func_name,
+                Arity::exact(parameters.len()),
+                expressions.len(),
+                span,
+            ));
+        }
+        if self.scopes.len() >= self.max_call_depth {
+            return Err(EvaluatorError::RecursionLimitExceeded(
+                func_name,
+                self.max_call_depth,
+                span,
+            ));
+        }
+        let mut scope = HashMap::with_capacity(parameters.len());
+        for (parameter, expression) in parameters.into_iter().zip(expressions) {
+            scope.insert(parameter, self.eval_expression(expression)?);
         }
+        self.scopes.push(scope);
+        let result = self.eval_expression(body);
+        self.scopes.pop();
+        result
     }
 }
@@ -0,0 +1,553 @@
+use std::collections::HashMap;
+use std::error::Error;
+
+use crate::evaluator::{Evaluator, error::EvaluatorError};
+use crate::numeric::{Arity, BuiltinFn};
+use crate::parser::ParserOptions;
+use crate::span::Span;
+
+struct TestBuiltins;
+
+impl BuiltinFn<f64> for TestBuiltins {
+    fn names(&self) -> &'static [&'static str] {
+        &["sqrt", "hypot", "max"]
+    }
+
+    fn arity(&self, name: &str) -> Option<Arity> {
+        match name {
+            "sqrt" => Some(Arity::exact(1)),
+            "hypot" => Some(Arity::exact(2)),
+            "max" => Some(Arity::at_least(1)),
+            _ => None,
+        }
+    }
+
+    fn call(&self, name: &str, args: &[f64]) -> Result<f64, String> {
+        match (name, args) {
+            ("sqrt", [x]) => Ok(x.sqrt()),
+            ("hypot", [x, y]) => Ok(x.hypot(*y)),
+            ("max", [first, rest @ ..]) => Ok(rest.iter().fold(*first, |acc, x| acc.max(*x))),
+            _ => Err(format!("unknown function `{name}`")),
+        }
+    }
+
+    fn constants(&self) -> HashMap<String, f64> {
+        HashMap::from([(String::from("pi"), std::f64::consts::PI)])
+    }
+}
+
+fn evaluate(input: &str) -> Result<Option<f64>, EvaluatorError> {
+    Evaluator::new(TestBuiltins).parse(input)
+}
+
+/// Macro to generate evaluator tests
+macro_rules! evaluator_test {
+    ($name:ident, $input:expr, $expected:expr) => {
+        #[test]
+        fn $name() {
+            assert_eq!(
+                evaluate($input).unwrap(),
+                Some($expected),
+                "Result mismatch in test '{}'\ninput: {}",
+                stringify!($name),
+                $input,
+            );
+        }
+    };
+}
+
+evaluator_test!(single_argument_call, "sqrt(16);", 4.0);
+evaluator_test!(two_argument_call, "hypot(3, 4);", 5.0);
+evaluator_test!(variadic_call, "max(1, 7, 3);", 7.0);
+evaluator_test!(arguments_are_expressions, "x = 2; max(x * 3, x + 1);", 6.0);
+evaluator_test!(factorial, "5!;", 120.0);
+evaluator_test!(zero_factorial, "0!;", 1.0);
+evaluator_test!(factorial_binds_before_negation, "-3! + 2;", -4.0);
+evaluator_test!(percent, "200 * 15%;", 30.0);
+evaluator_test!(percent_then_minus, "50% - 5;", -4.5);
+evaluator_test!(remainder, "7 % 3 + 7 mod 4;", 4.0);
+evaluator_test!(remainder_of_negative, "7 % -3 + 7 % +3;", -1.0);
+evaluator_test!(remainder_takes_divisor_sign, "-7 mod 3 + 7 mod -3;", 0.0);
+evaluator_test!(fractional_remainder, "7.5 mod 2;", 1.5);
+evaluator_test!(negation_binds_looser_than_power, "-2^2;", -4.0);
+evaluator_test!(negative_exponent, "2^-1 * 3;", 1.5);
+evaluator_test!(floor_division, "7 // 2 + -7 // 2;", -1.0);
+
+#[test]
+fn factorial_of_non_integers() {
+    let half = evaluate("0.5!;").unwrap().unwrap();
+    assert!((half - std::f64::consts::PI.sqrt() / 2.0).abs() < 1e-14);
+    let value = evaluate("4.2!;").unwrap().unwrap();
+    assert!((value - 32.578_096_050_331_95).abs() < 1e-12);
+}
+
+#[test]
+fn invalid_factorials_and_divisions() {
+    for input in [
+        "(-3)!;",
+        "(-0.5 - 0.5)!;",
+        "100000!;",
+        "5 mod 0;",
+        "5 // 0;",
+    ] {
+        assert!(
+            matches!(evaluate(input), Err(EvaluatorError::OperationFailed(_, _))),
+            "input: {input}"
+        );
+    }
+}
+
+#[test]
+fn spreadsheet_negation() {
+    let mut evaluator = Evaluator::new(TestBuiltins);
+    evaluator.set_parser_options(ParserOptions {
+        tight_negation: true,
+        ..ParserOptions::default()
+    });
+    assert_eq!(evaluator.parse("-2^2;").unwrap(), Some(4.0));
+    assert_eq!(evaluator.parse("2^-1;").unwrap(), Some(0.5));
+}
+
+#[test]
+fn arity_mismatch() {
+    assert!(matches!(
+        evaluate("hypot(3);"),
+        Err(EvaluatorError::ArityMismatch(name, _, 1, _)) if name == "hypot"
+    ));
+    assert!(matches!(
+        evaluate("max();"),
+        Err(EvaluatorError::ArityMismatch(name, _, 0, _)) if name == "max"
+    ));
+}
+
+#[test]
+fn unknown_function() {
+    assert!(matches!(
+        evaluate("nope(1);"),
+        Err(EvaluatorError::UnknownFunction(name, _)) if name == "nope"
+    ));
+}
+
+evaluator_test!(user_function, "f(x) = x^2 + 1; f(3);", 10.0);
+evaluator_test!(
+    user_function_multiple_parameters,
+    "area(w, h) = w * h; area(2, 3) + area(1, 1);",
+    7.0
+);
+evaluator_test!(
+    user_function_calls_builtin,
+    "len(x, y) = hypot(x, y); len(3, 4);",
+    5.0
+);
+evaluator_test!(
+    user_function_shadows_builtin,
+    "sqrt(x) = x; sqrt(16);",
+    16.0
+);
+evaluator_test!(
+    parameters_shadow_globals,
+    "x = 10; f(x) = x * 2; f(3) + x;",
+    16.0
+);
+evaluator_test!(body_sees_globals, "k = 5; f(x) = x + k; k = 6; f(1);", 7.0);
+evaluator_test!(
+    parameters_do_not_leak_into_callees,
+    "y = 1; g() = y; f(y) = g(); f(100);",
+    1.0
+);
+
+#[test]
+fn parameters_do_not_leak_into_globals() {
+    assert!(matches!(
+        evaluate("f(a) = a; f(1); a;"),
+        Err(EvaluatorError::UndefinedVariable(name, _)) if name == "a"
+    ));
+}
+
+#[test]
+fn user_function_arity_mismatch() {
+    assert!(matches!(
+        evaluate("f(x, y) = x; f(1);"),
+        Err(EvaluatorError::ArityMismatch(name, _, 1, _)) if name == "f"
+    ));
+}
+
+/// Runs `test` on a thread with the 8 MiB stack of a main thread, which the default call depth
+/// is meant for. Test threads get less.
+fn on_main_sized_stack(test: impl FnOnce() + Send + 'static) {
+    std::thread::Builder::new()
+        .stack_size(8 << 20)
+        .spawn(test)
+        .unwrap()
+        .join()
+        .unwrap();
+}
+
+#[test]
+fn moderately_deep_recursion_succeeds() {
+    on_main_sized_stack(|| {
+        let fact = evaluate("fact(n) = if n <= 1 then 1 else n * fact(n - 1); fact(70);");
+        assert!((fact.unwrap().unwrap() / 1.197_857_166_996_989e100 - 1.0).abs() < 1e-12);
+        assert_eq!(
+            evaluate("count(n) = if n <= 0 then 0 else 1 + count(n - 1); count(250);").unwrap(),
+            Some(250.0)
+        );
+    });
+}
+
+#[test]
+fn unbounded_recursion_is_reported() {
+    on_main_sized_stack(|| {
+        assert!(matches!(
+            evaluate("f(x) = f(x + 1); f(0);"),
+            Err(EvaluatorError::RecursionLimitExceeded(name, 256, _)) if name == "f"
+        ));
+        // a nested body needs more stack per call, this must still fail cleanly
+        assert!(matches!(
+            evaluate("g(x) = 1 + 2 * (3 - max(g(x + 1), 1)); g(0);"),
+            Err(EvaluatorError::RecursionLimitExceeded(name, _, _)) if name == "g"
+        ));
+    });
+}
+
+#[test]
+fn call_depth_is_configurable() {
+    let mut evaluator = Evaluator::new(TestBuiltins);
+    evaluator.set_max_call_depth(10);
+    assert_eq!(evaluator.max_call_depth(), 10);
+    evaluator
+        .parse("count(n) = if n <= 0 then 0 else 1 + count(n - 1);")
+        .unwrap();
+    assert_eq!(evaluator.parse("count(9);").unwrap(), Some(9.0));
+    let error = evaluator.parse("count(10);").unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "recursion limit exceeded in `count` (more than 10 nested calls)"
+    );
+}
+
+#[test]
+fn error_spans() {
+    let span_of = |input| evaluate(input).unwrap_err().span();
+    assert_eq!(span_of("1 + foo;"), Some(Span::new(4, 7)));
+    assert_eq!(span_of("x = 1 / (2 - 2);"), Some(Span::new(4, 15)));
+    assert_eq!(span_of("2 * hypot(1);"), Some(Span::new(4, 12)));
+    assert_eq!(span_of("pi = 3;"), Some(Span::new(0, 2)));
+    assert_eq!(span_of("1 +;"), Some(Span::new(3, 4)));
+    assert_eq!(span_of("2 + (1 - 2)!;"), Some(Span::new(4, 12)));
+}
+
+#[test]
+fn error_messages_and_sources() {
+    let error = evaluate("1 + &;").unwrap_err();
+    assert_eq!(error.to_string(), "syntax error");
+    let parser_error = error.source().expect("parser error as source");
+    assert_eq!(parser_error.to_string(), "invalid token");
+    let lexer_error = parser_error.source().expect("lexer error as source");
+    assert_eq!(lexer_error.to_string(), "unexpected character `&`");
+    assert!(lexer_error.source().is_none());
+
+    assert_eq!(
+        evaluate("hypot(1);").unwrap_err().to_string(),
+        "function `hypot` takes 2 argument(s) but 1 were given"
+    );
+    assert_eq!(
+        evaluate("2 * (3;")
+            .unwrap_err()
+            .source()
+            .unwrap()
+            .to_string(),
+        "unexpected `;`"
+    );
+}
+
+#[test]
+fn errors_convert_into_boxed_errors() {
+    let boxed: Box<dyn Error + Send + Sync> = evaluate("x;").unwrap_err().into();
+    assert_eq!(boxed.to_string(), "undefined variable `x`");
+}
+
+evaluator_test!(
+    comparisons,
+    "(1 < 2) + (2 <= 2) + (3 > 4) + (3 >= 4) + (5 == 5) + (5 != 5);",
+    3.0
+);
+evaluator_test!(
+    logical_operators,
+    "(1 && 0) + (1 || 0) * 2 + !0 * 4 + !7 * 8;",
+    6.0
+);
+evaluator_test!(conditional, "x = -3; if x < 0 then -x else x;", 3.0);
+evaluator_test!(
+    piecewise_function,
+    "tax(x) = if x <= 10000 then 0 else if x <= 40000 then (x - 10000) * 0.2 else 6000 + (x - 40000) * 0.4; tax(5000) + tax(20000) + tax(50000);",
+    12000.0
+);
+evaluator_test!(
+    recursion_with_base_case,
+    "fact(n) = if n <= 1 then 1 else n * fact(n - 1); fact(10);",
+    3628800.0
+);
+evaluator_test!(short_circuit_and, "0 && undefined;", 0.0);
+evaluator_test!(short_circuit_or, "2 || undefined;", 1.0);
+evaluator_test!(unevaluated_branch, "if 1 then 2 else undefined;", 2.0);
+
+#[test]
+fn variable_accessors() {
+    let mut evaluator = Evaluator::new(TestBuiltins);
+    evaluator.parse("x = 2;").unwrap();
+    evaluator.set_variable("y".into(), 3.0).unwrap();
+    assert_eq!(evaluator.variables().get("x"), Some(&2.0));
+    assert_eq!(evaluator.parse("x * y;").unwrap(), Some(6.0));
+    assert!(matches!(
+        evaluator.set_variable("pi".into(), 3.0),
+        Err(EvaluatorError::InvalidAssignment(..))
+    ));
+}
+
+#[test]
+fn session_accessors() {
+    let mut evaluator = Evaluator::new(TestBuiltins);
+    evaluator.parse("x = 2; f(a, b) = a * b;").unwrap();
+    assert_eq!(evaluator.variable("x"), Some(&2.0));
+    assert_eq!(evaluator.variable("pi"), Some(&std::f64::consts::PI));
+    assert_eq!(evaluator.functions()["f"].parameters(), ["a", "b"]);
+    assert_eq!(evaluator.functions()["f"].source(), "a * b");
+    assert_eq!(evaluator.builtins().names(), ["sqrt", "hypot", "max"]);
+
+    assert_eq!(evaluator.remove_variable("x"), Some(2.0));
+    assert_eq!(evaluator.remove_variable("x"), None);
+    assert!(matches!(
+        evaluator.parse("x;"),
+        Err(EvaluatorError::UndefinedVariable(..))
+    ));
+
+    evaluator.parse("y = 1;").unwrap();
+    evaluator.reset();
+    assert!(evaluator.variables().is_empty());
+    assert!(evaluator.functions().is_empty());
+    assert!(evaluator.constants().contains_key("pi"));
+}
+
+#[test]
+fn evaluate_each_reports_every_expression() {
+    let mut evaluator = Evaluator::new(TestBuiltins);
+    let mut results = Vec::new();
+    evaluator
+        .evaluate_each("x = 2; x * 3; ; x + 1", |_, result| results.push(result))
+        .unwrap();
+    assert_eq!(results, [6.0, 3.0]);
+
+    results.clear();
+    let error = evaluator.evaluate_each("y = 5; y; z; 1", |_, result| results.push(result));
+    assert!(matches!(error, Err(EvaluatorError::UndefinedVariable(..))));
+    assert_eq!(
+        results,
+        [5.0],
+        "results before the failing statement are kept"
+    );
+    assert_eq!(evaluator.variable("y"), Some(&5.0));
+}
+
+#[test]
+fn evaluate_all_goes_on_after_errors() {
+    let mut evaluator = Evaluator::new(TestBuiltins);
+    let mut results = Vec::new();
+    let mut errors = Vec::new();
+    let script = "a = 1 # one\nb = ) 2\nc = a * 3; d = sqrt(1, 2)\nf(x) = x +\n  a; z\nf(c)";
+    evaluator.evaluate_all(
+        script,
+        |_, result| results.push(result),
+        |error| errors.push(error.span().unwrap()),
+    );
+    assert_eq!(results, [4.0]);
+    assert_eq!(
+        errors,
+        [Span::new(16, 17), Span::new(35, 45), Span::new(62, 63)]
+    );
+    assert_eq!(evaluator.variable("c"), Some(&3.0));
+    assert_eq!(evaluator.variable("b"), None);
+}
+
+#[test]
+fn result_history() {
+    let mut evaluator = Evaluator::new(TestBuiltins);
+    let mut numbers = Vec::new();
+    evaluator
+        .evaluate_each("1 + 1; x = 5; ans * 10; $1 + $2", |number, _| {
+            numbers.push(number)
+        })
+        .unwrap();
+    assert_eq!(numbers, [1, 2, 3]);
+    assert_eq!(evaluator.last_result(), Some(&22.0));
+    assert_eq!(evaluator.parse("ans + $3;").unwrap(), Some(44.0));
+    assert!(matches!(
+        evaluator.parse("$5;"),
+        Err(EvaluatorError::UnknownResult(5, span)) if span == Span::new(0, 2)
+    ));
+    assert!(matches!(
+        evaluator.parse("$0;"),
+        Err(EvaluatorError::UnknownResult(0, _))
+    ));
+}
+
+#[test]
+fn result_history_limit() {
+    let mut evaluator = Evaluator::new(TestBuiltins);
+    evaluator.set_history_limit(2);
+    evaluator.parse("10; 20; 30;").unwrap();
+    assert!(evaluator.result(1).is_none());
+    assert_eq!(evaluator.result(2), Some(&20.0));
+    assert_eq!(evaluator.parse("$3;").unwrap(), Some(30.0));
+    assert!(matches!(
+        evaluator.parse("$2;"),
+        Err(EvaluatorError::UnknownResult(2, _))
+    ));
+}
+
+#[test]
+fn ans_needs_a_result() {
+    assert!(matches!(
+        evaluate("ans;"),
+        Err(EvaluatorError::UndefinedVariable(name, _)) if name == "ans"
+    ));
+    assert_eq!(evaluate("ans = 3; ans;").unwrap(), Some(3.0));
+}
+
+/// Inputs whose compiled value for `x` is checked against evaluating them with `x` assigned.
+const COMPILED: &[&str] = &[
+    "x ^ 2 + 3 * x - 1",
+    "sqrt(x) + hypot(x, 1) + max(x, 2, 3, 4)",
+    "if x > 2 then -x else x / 2",
+    "x > 1 && x < 3 || x == 5",
+    "pi * x",
+    "f(x, 2) + k",
+    "x! + x% - x mod 2 + x // 2",
+    "f(x + 1, f(x, x - 1)) - f(2 * x, sqrt(x))",
+];
+
+#[test]
+fn compiled_expressions_match_evaluation() {
+    let mut evaluator = Evaluator::new(TestBuiltins);
+    evaluator.parse("k = 10; f(a, b) = a * b + k;").unwrap();
+    for input in COMPILED {
+        let compiled = evaluator.compile(input, &["x"]).unwrap();
+        for x in [0.5, 2.0, 3.0, 5.0] {
+            let mut session = Evaluator::new(TestBuiltins);
+            session.parse("k = 10; f(a, b) = a * b + k;").unwrap();
+            session.set_variable(String::from("x"), x).unwrap();
+            assert_eq!(
+                compiled.eval(&[x]).unwrap(),
+                session.parse(input).unwrap().unwrap(),
+                "compiled {input} differs at {x}",
+            );
+        }
+    }
+}
+
+#[test]
+fn compiled_arguments_fail_like_evaluated_ones() {
+    let definitions = "g(a) = 1; h(a, b) = if b then a else 0;";
+    let mut evaluator = Evaluator::new(TestBuiltins);
+    evaluator.parse(definitions).unwrap();
+    for input in [
+        "g(1 / 0) + x",
+        "g(1 / x) * 2",
+        "h(1 / x, x > 1)",
+        "g(g(0 ^ -x))",
+    ] {
+        let compiled = evaluator.compile(input, &["x"]).unwrap();
+        for x in [0.0, 2.0] {
+            let mut session = Evaluator::new(TestBuiltins);
+            session.parse(definitions).unwrap();
+            session.set_variable(String::from("x"), x).unwrap();
+            let expected = session.parse(input).map(Option::unwrap);
+            match (compiled.eval(&[x]), expected) {
+                (Ok(value), Ok(expected)) => assert_eq!(value, expected, "{input} at {x}"),
+                (Err(error), Err(expected)) => {
+                    assert_eq!(error.to_string(), expected.to_string(), "{input} at {x}");
+                    assert_eq!(error.span(), expected.span(), "{input} at {x}");
+                }
+                (value, expected) => panic!("{input} at {x}: {value:?} but {expected:?}"),
+            }
+        }
+    }
+}
+
+#[test]
+fn compiled_parameters_are_slots() {
+    let evaluator = Evaluator::new(TestBuiltins);
+    let compiled = evaluator.compile("a - b", &["b", "a"]).unwrap();
+    assert_eq!(compiled.parameters(), ["b", "a"]);
+    assert_eq!(compiled.eval(&[1.0, 10.0]).unwrap(), 9.0);
+    assert_eq!(compiled.eval(&[4.0, 1.0]).unwrap(), -3.0);
+    assert!(matches!(
+        compiled.eval(&[1.0]),
+        Err(EvaluatorError::ParameterCountMismatch(2, 1))
+    ));
+    assert!(matches!(
+        compiled.eval(&[1.0, 2.0, 3.0]),
+        Err(EvaluatorError::ParameterCountMismatch(2, 3))
+    ));
+}
+
+#[test]
+fn compiling_fixes_the_session() {
+    let mut evaluator = Evaluator::new(TestBuiltins);
+    evaluator.parse("k = 2; 7;").unwrap();
+    let compiled = evaluator.compile("x * k + ans + $1", &["x"]).unwrap();
+    assert_eq!(compiled.eval(&[1.0]).unwrap(), 16.0);
+    // parameters shadow session variables
+    let compiled = evaluator.compile("k", &["k"]).unwrap();
+    assert_eq!(compiled.eval(&[5.0]).unwrap(), 5.0);
+}
+
+#[test]
+fn compile_errors() {
+    let mut evaluator = Evaluator::new(TestBuiltins);
+    evaluator.parse("f(x) = f(x - 1); g(x) = f(x);").unwrap();
+    let compile = |input, parameters: &[&str]| evaluator.compile(input, parameters).unwrap_err();
+    assert!(matches!(
+        compile("x + y", &["x"]),
+        EvaluatorError::UndefinedVariable(name, _) if name == "y"
+    ));
+    assert!(matches!(
+        compile("1 + g(2)", &[]),
+        EvaluatorError::RecursiveFunction(name, _) if name == "f"
+    ));
+    assert!(matches!(
+        compile("x = 1", &[]),
+        EvaluatorError::NotAnExpression(_)
+    ));
+    assert!(matches!(
+        compile("1; 2", &[]),
+        EvaluatorError::NotAnExpression(_)
+    ));
+    assert!(matches!(
+        compile("nope(x)", &["x"]),
+        EvaluatorError::UnknownFunction(name, _) if name == "nope"
+    ));
+    assert!(matches!(
+        compile("hypot(x)", &["x"]),
+        EvaluatorError::ArityMismatch(name, _, 1, _) if name == "hypot"
+    ));
+    assert!(matches!(
+        compile("pi", &["pi"]),
+        EvaluatorError::InvalidAssignment(name, _) if name == "pi"
+    ));
+    assert!(matches!(
+        compile("$3", &[]),
+        EvaluatorError::UnknownResult(3, _)
+    ));
+}
+
+#[test]
+fn compiled_evaluation_errors_have_spans() {
+    let evaluator = Evaluator::new(TestBuiltins);
+    let compiled = evaluator.compile("1 + 2 / x", &["x"]).unwrap();
+    assert_eq!(compiled.eval(&[2.0]).unwrap(), 2.0);
+    assert_eq!(
+        compiled.eval(&[0.0]).unwrap_err().span(),
+        Some(Span::new(4, 9))
+    );
+}
@@ -0,0 +1,169 @@
+use crate::format::PrettyDisplay;
+use crate::lexer::token::Operator;
+use crate::numeric::NumericValue;
+use crate::parser::ast::{Expression, ExpressionKind, Statement, UnaryOp};
+use crate::parser::printer::{
+    self, ATOM, POSTFIX, Side, precedence, prefix_operand_needs_parentheses,
+};
+
+use super::{greek, operator_name, parenthesize_operand};
+
+/// Renders `expression` as a LaTeX formula for math mode, e.g. `\frac{a}{b}` for `a / b`.
+pub fn latex<N: NumericValue + PrettyDisplay>(expression: &Expression<N>) -> String {
+    match &expression.kind {
+        ExpressionKind::Number(value) => number(value),
+        ExpressionKind::Variable(name) => identifier(name),
+        ExpressionKind::ResultRef(number) => format!(r"\${number}"),
+        ExpressionKind::Unary(op, operand) if op.is_postfix() => {
+            let symbol = if *op == UnaryOp::Percent { r"\%" } else { "!" };
+            format!("{}{symbol}", group(operand, precedence(operand) < POSTFIX))
+        }
+        ExpressionKind::Unary(op, operand) => {
+            let symbol = match op {
+                UnaryOp::Negative => "-",
+                UnaryOp::Positive => "+",
+                UnaryOp::Not => r"\neg ",
+                UnaryOp::Factorial | UnaryOp::Percent | UnaryOp::_Marker(_) => unreachable!(),
+            };
+            format!(
+                "{symbol}{}",
+                group(operand, prefix_operand_needs_parentheses(op, operand))
+            )
+        }
+        ExpressionKind::Binary(left, Operator::Slash, right) => {
+            format!(r"\frac{{{}}}{{{}}}", latex(left), latex(right))
+        }
+        ExpressionKind::Binary(left, Operator::FloorDiv, right) => format!(
+            r"\left\lfloor \frac{{{}}}{{{}}} \right\rfloor",
+            latex(left),
+            latex(right)
+        ),
+        ExpressionKind::Binary(base, Operator::Caret, exponent) => {
+            let base = group(base, precedence(base) < ATOM);
+            format!("{base}^{{{}}}", latex(exponent))
+        }
+        ExpressionKind::Binary(left, op, right) => {
+            let symbol = match op {
+                Operator::Plus => "+",
+                Operator::Minus => "-",
+                Operator::Star => r"\cdot",
+                Operator::Rem => r"\bmod",
+                Operator::Equal => "=",
+                Operator::NotEqual => r"\neq",
+                Operator::Less => "<",
+                Operator::LessEqual => r"\leq",
+                Operator::Greater => ">",
+                Operator::GreaterEqual => r"\geq",
+                Operator::And => r"\land",
+                Operator::Or => r"\lor",
+                Operator::Slash
+                | Operator::FloorDiv
+                | Operator::Caret
+                | Operator::Not
+                | Operator::Percent => unreachable!(),
+            };
+            binary(left, *op, symbol, right)
+        }
+        ExpressionKind::Call(name, arguments) => call(name, arguments),
+        ExpressionKind::Conditional(condition, then_branch, else_branch) => format!(
+            r"\begin{{cases}} {} & \text{{if }} {} \\ {} & \text{{otherwise}} \end{{cases}}",
+            latex(then_branch),
+            latex(condition),
+            latex(else_branch)
+        ),
+    }
+}
+
+/// Renders `statement` as a LaTeX formula, or returns `None` for an empty statement.
+pub fn latex_statement<N: NumericValue + PrettyDisplay>(
+    statement: &Statement<N>,
+) -> Option<String> {
+    match statement {
+        Statement::Assignment(name, value) => {
+            Some(format!("{} = {}", identifier(&name.node), latex(value)))
+        }
+        Statement::FunctionDefinition(name, parameters, body) => {
+            let parameters: Vec<_> = parameters.iter().map(|name| identifier(name)).collect();
+            Some(format!(
+                r"{}\left({}\right) = {}",
+                function_name(&name.node),
+                parameters.join(", "),
+                latex(body)
+            ))
+        }
+        Statement::Expression(expression) => Some(latex(expression)),
+        Statement::Empty => None,
+    }
+}
+
+fn binary<N: NumericValue + PrettyDisplay>(
+    left: &Expression<N>,
+    op: Operator,
+    symbol: &str,
+    right: &Expression<N>,
+) -> String {
+    format!(
+        "{} {symbol} {}",
+        group(left, parenthesize_operand(left, op, Side::Left)),
+        group(right, parenthesize_operand(right, op, Side::Right))
+    )
+}
+
+fn call<N: NumericValue + PrettyDisplay>(name: &str, arguments: &[Expression<N>]) -> String {
+    match (name, arguments) {
+        ("sqrt", [x]) => format!(r"\sqrt{{{}}}", latex(x)),
+        ("abs", [x]) => format!(r"\left|{}\right|", latex(x)),
+        ("log", [x, base]) => format!(r"\log_{{{}}}\left({}\right)", latex(base), latex(x)),
+        _ => {
+            let arguments: Vec<_> = arguments.iter().map(latex).collect();
+            format!(
+                r"{}\left({}\right)",
+                function_name(name),
+                arguments.join(", ")
+            )
+        }
+    }
+}
+
+fn group<N: NumericValue + PrettyDisplay>(
+    expression: &Expression<N>,
+    parenthesize: bool,
+) -> String {
+    match parenthesize {
+        true => format!(r"\left({}\right)", latex(expression)),
+        false => latex(expression),
+    }
+}
+
+fn number<N: PrettyDisplay>(value: &N) -> String {
+    let text = printer::number(value);
+    let (sign, magnitude) = match text.strip_prefix('-') {
+        Some(magnitude) => ("-", magnitude),
+        None => ("", text.as_str()),
+    };
+    match magnitude.split_once('/') {
+        Some((numerator, denominator)) => format!(r"{sign}\frac{{{numerator}}}{{{denominator}}}"),
+        None => text,
+    }
+}
+
+/// A variable name, in italics if it is a single letter and upright otherwise.
+fn identifier(name: &str) -> String {
+    if let Some((command, _)) = greek(name) {
+        return command.to_owned();
+    }
+    match name.len() {
+        1 => name.to_owned(),
+        _ => format!(r"\mathrm{{{}}}", name.replace('_', r"\_")),
+    }
+}
+
+fn function_name(name: &str) -> String {
+    if let Some(command) = operator_name(name) {
+        return command.to_owned();
+    }
+    match name.len() {
+        1 => name.to_owned(),
+        _ => format!(r"\operatorname{{{}}}", name.replace('_', r"\_")),
+    }
+}
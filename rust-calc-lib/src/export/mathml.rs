@@ -0,0 +1,196 @@
+use crate::format::PrettyDisplay;
+use crate::lexer::token::Operator;
+use crate::numeric::NumericValue;
+use crate::parser::ast::{Expression, ExpressionKind, Statement, UnaryOp};
+use crate::parser::printer::{
+    self, ATOM, POSTFIX, Side, precedence, prefix_operand_needs_parentheses,
+};
+
+use super::{greek, operator_name, parenthesize_operand};
+
+/// The invisible operator MathML puts between a function name and its arguments.
+const FUNCTION_APPLICATION: &str = "<mo>&#x2061;</mo>";
+
+/// Renders `expression` as a presentation MathML `<math>` element.
+pub fn mathml<N: NumericValue + PrettyDisplay>(expression: &Expression<N>) -> String {
+    math(&element(expression))
+}
+
+/// Renders `statement` as a presentation MathML `<math>` element, or returns `None` for an
+/// empty statement.
+pub fn mathml_statement<N: NumericValue + PrettyDisplay>(
+    statement: &Statement<N>,
+) -> Option<String> {
+    let content = match statement {
+        Statement::Assignment(name, value) => {
+            format!(
+                "<mrow>{}<mo>=</mo>{}</mrow>",
+                identifier(&name.node),
+                element(value)
+            )
+        }
+        Statement::FunctionDefinition(name, parameters, body) => {
+            let parameters: Vec<_> = parameters.iter().map(|name| identifier(name)).collect();
+            format!(
+                "<mrow>{}{FUNCTION_APPLICATION}{}<mo>=</mo>{}</mrow>",
+                function_name(&name.node),
+                parenthesized(&parameters.join("<mo>,</mo>")),
+                element(body)
+            )
+        }
+        Statement::Expression(expression) => element(expression),
+        Statement::Empty => return None,
+    };
+    Some(math(&content))
+}
+
+fn math(content: &str) -> String {
+    format!(r#"<math xmlns="http://www.w3.org/1998/Math/MathML">{content}</math>"#)
+}
+
+/// Renders `expression` as a single MathML element.
+fn element<N: NumericValue + PrettyDisplay>(expression: &Expression<N>) -> String {
+    match &expression.kind {
+        ExpressionKind::Number(value) => number(value),
+        ExpressionKind::Variable(name) => identifier(name),
+        ExpressionKind::ResultRef(number) => format!("<mi>${number}</mi>"),
+        ExpressionKind::Unary(op, operand) if op.is_postfix() => {
+            let operand = group(operand, precedence(operand) < POSTFIX);
+            format!("<mrow>{operand}<mo>{op}</mo></mrow>")
+        }
+        ExpressionKind::Unary(op, operand) => {
+            let symbol = match op {
+                UnaryOp::Negative => "-",
+                UnaryOp::Positive => "+",
+                UnaryOp::Not => "¬",
+                UnaryOp::Factorial | UnaryOp::Percent | UnaryOp::_Marker(_) => unreachable!(),
+            };
+            let operand = group(operand, prefix_operand_needs_parentheses(op, operand));
+            format!("<mrow><mo>{symbol}</mo>{operand}</mrow>")
+        }
+        ExpressionKind::Binary(left, Operator::Slash, right) => {
+            format!("<mfrac>{}{}</mfrac>", element(left), element(right))
+        }
+        ExpressionKind::Binary(left, Operator::FloorDiv, right) => format!(
+            "<mrow><mo>⌊</mo><mfrac>{}{}</mfrac><mo>⌋</mo></mrow>",
+            element(left),
+            element(right)
+        ),
+        ExpressionKind::Binary(base, Operator::Caret, exponent) => {
+            let base = group(base, precedence(base) < ATOM);
+            format!("<msup>{base}{}</msup>", element(exponent))
+        }
+        ExpressionKind::Binary(left, op, right) => {
+            let symbol = match op {
+                Operator::Plus => "+",
+                Operator::Minus => "-",
+                Operator::Star => "⋅",
+                Operator::Rem => "mod",
+                Operator::Equal => "=",
+                Operator::NotEqual => "≠",
+                Operator::Less => "&lt;",
+                Operator::LessEqual => "≤",
+                Operator::Greater => "&gt;",
+                Operator::GreaterEqual => "≥",
+                Operator::And => "∧",
+                Operator::Or => "∨",
+                Operator::Slash
+                | Operator::FloorDiv
+                | Operator::Caret
+                | Operator::Not
+                | Operator::Percent => unreachable!(),
+            };
+            format!(
+                "<mrow>{}<mo>{symbol}</mo>{}</mrow>",
+                group(left, parenthesize_operand(left, *op, Side::Left)),
+                group(right, parenthesize_operand(right, *op, Side::Right))
+            )
+        }
+        ExpressionKind::Call(name, arguments) => call(name, arguments),
+        ExpressionKind::Conditional(condition, then_branch, else_branch) => format!(
+            "<mrow><mo>{{</mo><mtable>\
+             <mtr><mtd>{}</mtd><mtd><mtext>if&#xA0;</mtext>{}</mtd></mtr>\
+             <mtr><mtd>{}</mtd><mtd><mtext>otherwise</mtext></mtd></mtr>\
+             </mtable></mrow>",
+            element(then_branch),
+            element(condition),
+            element(else_branch)
+        ),
+    }
+}
+
+fn call<N: NumericValue + PrettyDisplay>(name: &str, arguments: &[Expression<N>]) -> String {
+    match (name, arguments) {
+        ("sqrt", [x]) => format!("<msqrt>{}</msqrt>", element(x)),
+        ("abs", [x]) => format!("<mrow><mo>|</mo>{}<mo>|</mo></mrow>", element(x)),
+        ("log", [x, base]) => format!(
+            "<mrow><msub><mi>log</mi>{}</msub>{FUNCTION_APPLICATION}{}</mrow>",
+            element(base),
+            parenthesized(&element(x))
+        ),
+        _ => {
+            let arguments: Vec<_> = arguments.iter().map(element).collect();
+            format!(
+                "<mrow>{}{FUNCTION_APPLICATION}{}</mrow>",
+                function_name(name),
+                parenthesized(&arguments.join("<mo>,</mo>"))
+            )
+        }
+    }
+}
+
+fn group<N: NumericValue + PrettyDisplay>(
+    expression: &Expression<N>,
+    parenthesize: bool,
+) -> String {
+    match parenthesize {
+        true => parenthesized(&element(expression)),
+        false => element(expression),
+    }
+}
+
+fn parenthesized(content: &str) -> String {
+    format!("<mrow><mo>(</mo>{content}<mo>)</mo></mrow>")
+}
+
+/// Writes a number, splitting the signs and operators of values like `-1/3` or `1 - 2i` into
+/// operator elements.
+fn number<N: PrettyDisplay>(value: &N) -> String {
+    let text = printer::number(value);
+    let mut parts: Vec<String> = Vec::new();
+    for word in text.split_whitespace() {
+        if let Some(magnitude) = word.strip_prefix('-').filter(|rest| !rest.is_empty()) {
+            parts.push(String::from("<mo>-</mo>"));
+            parts.push(unsigned(magnitude));
+        } else if word == "+" || word == "-" {
+            parts.push(format!("<mo>{word}</mo>"));
+        } else {
+            parts.push(unsigned(word));
+        }
+    }
+    match parts.as_slice() {
+        [single] => single.clone(),
+        _ => format!("<mrow>{}</mrow>", parts.concat()),
+    }
+}
+
+fn unsigned(number: &str) -> String {
+    match number.split_once('/') {
+        Some((numerator, denominator)) => {
+            format!("<mfrac><mn>{numerator}</mn><mn>{denominator}</mn></mfrac>")
+        }
+        None => format!("<mn>{number}</mn>"),
+    }
+}
+
+fn identifier(name: &str) -> String {
+    match greek(name) {
+        Some((_, letter)) => format!("<mi>{letter}</mi>"),
+        None => format!("<mi>{name}</mi>"),
+    }
+}
+
+fn function_name(name: &str) -> String {
+    let name = operator_name(name).map_or(name, |command| &command[1..]);
+    format!("<mi>{name}</mi>")
+}
@@ -0,0 +1,151 @@
+mod latex;
+mod mathml;
+#[cfg(test)]
+mod tests;
+
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+use crate::format::PrettyDisplay;
+use crate::lexer::token::Operator;
+use crate::numeric::NumericValue;
+use crate::parser::ast::{Expression, ExpressionKind, Statement, UnaryOp};
+use crate::parser::printer::{self, Side, needs_parentheses};
+
+pub use latex::{latex, latex_statement};
+pub use mathml::{mathml, mathml_statement};
+
+/// A typesetting language formulas can be exported to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Latex,
+    MathMl,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 2] = [ExportFormat::Latex, ExportFormat::MathMl];
+
+    /// Renders `expression` in this format.
+    pub fn expression<N: NumericValue + PrettyDisplay>(
+        &self,
+        expression: &Expression<N>,
+    ) -> String {
+        match self {
+            ExportFormat::Latex => latex(expression),
+            ExportFormat::MathMl => mathml(expression),
+        }
+    }
+
+    /// Renders `statement` in this format, or returns `None` for an empty statement.
+    pub fn statement<N: NumericValue + PrettyDisplay>(
+        &self,
+        statement: &Statement<N>,
+    ) -> Option<String> {
+        match self {
+            ExportFormat::Latex => latex_statement(statement),
+            ExportFormat::MathMl => mathml_statement(statement),
+        }
+    }
+}
+
+impl Display for ExportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ExportFormat::Latex => "latex",
+            ExportFormat::MathMl => "mathml",
+        })
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        ExportFormat::ALL
+            .into_iter()
+            .find(|format| format.to_string() == s)
+            .ok_or_else(|| {
+                let formats: Vec<_> = ExportFormat::ALL
+                    .iter()
+                    .map(ExportFormat::to_string)
+                    .collect();
+                format!(
+                    "unknown format `{s}`, expected one of {}",
+                    formats.join(", ")
+                )
+            })
+    }
+}
+
+/// Functions typeset as an upright operator name, with their LaTeX command.
+const OPERATOR_NAMES: &[(&str, &str)] = &[
+    ("sin", r"\sin"),
+    ("cos", r"\cos"),
+    ("tan", r"\tan"),
+    ("asin", r"\arcsin"),
+    ("acos", r"\arccos"),
+    ("atan", r"\arctan"),
+    ("sinh", r"\sinh"),
+    ("cosh", r"\cosh"),
+    ("tanh", r"\tanh"),
+    ("exp", r"\exp"),
+    ("ln", r"\ln"),
+    ("log", r"\log"),
+    ("max", r"\max"),
+    ("min", r"\min"),
+    ("arg", r"\arg"),
+    ("gcd", r"\gcd"),
+];
+
+/// Greek letter names that are typeset as the letter itself, with their LaTeX command and
+/// character.
+const GREEK: &[(&str, &str, char)] = &[
+    ("alpha", r"\alpha", 'α'),
+    ("beta", r"\beta", 'β'),
+    ("gamma", r"\gamma", 'γ'),
+    ("delta", r"\delta", 'δ'),
+    ("epsilon", r"\epsilon", 'ε'),
+    ("theta", r"\theta", 'θ'),
+    ("lambda", r"\lambda", 'λ'),
+    ("mu", r"\mu", 'μ'),
+    ("pi", r"\pi", 'π'),
+    ("rho", r"\rho", 'ρ'),
+    ("sigma", r"\sigma", 'σ'),
+    ("tau", r"\tau", 'τ'),
+    ("phi", r"\phi", 'φ'),
+    ("omega", r"\omega", 'ω'),
+];
+
+fn greek(name: &str) -> Option<(&'static str, char)> {
+    GREEK
+        .iter()
+        .find(|(greek, _, _)| *greek == name)
+        .map(|(_, command, letter)| (*command, *letter))
+}
+
+/// The LaTeX command of a function typeset as an upright operator name, like `\sin`.
+fn operator_name(name: &str) -> Option<&'static str> {
+    OPERATOR_NAMES
+        .iter()
+        .find(|(known, _)| *known == name)
+        .map(|(_, command)| *command)
+}
+
+/// Whether `operand` is typeset in parentheses on the `side` of the binary operator `op`.
+/// Fractions are set apart by their bar and never need them, while a signed right operand gets
+/// them for readability, as in `a - (-b)`.
+fn parenthesize_operand<N: NumericValue + PrettyDisplay>(
+    operand: &Expression<N>,
+    op: Operator,
+    side: Side,
+) -> bool {
+    let signed = match &operand.kind {
+        ExpressionKind::Unary(op, _) => matches!(op, UnaryOp::Negative | UnaryOp::Positive),
+        ExpressionKind::Number(value) => printer::number(value).starts_with('-'),
+        _ => false,
+    };
+    match &operand.kind {
+        ExpressionKind::Binary(_, Operator::Slash, _) => false,
+        _ => needs_parentheses(operand, op, side) || side == Side::Right && signed,
+    }
+}
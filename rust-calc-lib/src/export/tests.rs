@@ -0,0 +1,199 @@
+use crate::export::{ExportFormat, latex, latex_statement, mathml, mathml_statement};
+use crate::numeric::rational::Rational;
+use crate::parser::Parser;
+use crate::parser::ast::{Expression, ExpressionKind, Statement};
+
+fn statement(input: &str) -> Statement<f64> {
+    Parser::new(input).parse_program().unwrap().remove(0)
+}
+
+fn expression(input: &str) -> Expression<f64> {
+    match statement(input) {
+        Statement::Expression(expression) => expression,
+        statement => panic!("`{input}` is not an expression: {statement:?}"),
+    }
+}
+
+/// Macro to generate LaTeX export tests
+macro_rules! latex_test {
+    ($name:ident, $input:expr, $expected:expr) => {
+        #[test]
+        fn $name() {
+            assert_eq!(
+                latex(&expression($input)),
+                $expected,
+                "LaTeX mismatch in test '{}'\ninput: {}",
+                stringify!($name),
+                $input,
+            );
+        }
+    };
+}
+
+/// Macro to generate MathML export tests, `$expected` being the content of the `<math>` element
+macro_rules! mathml_test {
+    ($name:ident, $input:expr, $expected:expr) => {
+        #[test]
+        fn $name() {
+            assert_eq!(
+                mathml(&expression($input)),
+                format!(
+                    r#"<math xmlns="http://www.w3.org/1998/Math/MathML">{}</math>"#,
+                    $expected
+                ),
+                "MathML mismatch in test '{}'\ninput: {}",
+                stringify!($name),
+                $input,
+            );
+        }
+    };
+}
+
+latex_test!(latex_fraction, "a / b", r"\frac{a}{b}");
+latex_test!(latex_power, "x ^ 2", "x^{2}");
+latex_test!(latex_sqrt, "sqrt(x)", r"\sqrt{x}");
+latex_test!(latex_sin, "sin(x)", r"\sin\left(x\right)");
+latex_test!(latex_inverse_trig, "asin(x)", r"\arcsin\left(x\right)");
+latex_test!(latex_log_base, "log(x, 2)", r"\log_{2}\left(x\right)");
+latex_test!(latex_abs, "abs(x - 1)", r"\left|x - 1\right|");
+latex_test!(
+    latex_user_function,
+    "f(x, y) + area(r)",
+    r"f\left(x, y\right) + \operatorname{area}\left(r\right)"
+);
+latex_test!(latex_product, "2 * x", r"2 \cdot x");
+latex_test!(
+    latex_precedence,
+    "(a + b) * c - (d - e)",
+    r"\left(a + b\right) \cdot c - \left(d - e\right)"
+);
+latex_test!(
+    latex_fraction_needs_no_parentheses,
+    "(a + b) / (c * d) * e",
+    r"\frac{a + b}{c \cdot d} \cdot e"
+);
+latex_test!(latex_power_base, "(x + 1) ^ 2", r"\left(x + 1\right)^{2}");
+latex_test!(latex_unary_power_base, "(-x) ^ 2", r"\left(-x\right)^{2}");
+latex_test!(
+    latex_negated_power,
+    "-x ^ 2 + !(y ^ 2)",
+    r"-x^{2} + \neg \left(y^{2}\right)"
+);
+latex_test!(
+    latex_fraction_power_base,
+    "(1 / x) ^ 2",
+    r"\left(\frac{1}{x}\right)^{2}"
+);
+latex_test!(latex_right_associative_power, "2 ^ 3 ^ x", "2^{3^{x}}");
+latex_test!(
+    latex_negated_right_operand,
+    "a - -b",
+    r"a - \left(-b\right)"
+);
+latex_test!(
+    latex_comparisons,
+    "a <= b && c != d || !e",
+    r"a \leq b \land c \neq d \lor \neg e"
+);
+latex_test!(
+    latex_names,
+    "pi * theta_1 + x",
+    r"\pi \cdot \mathrm{theta\_1} + x"
+);
+latex_test!(latex_result_ref, "$2 + 1", r"\$2 + 1");
+latex_test!(
+    latex_conditional,
+    "if x < 0 then -x else x",
+    r"\begin{cases} -x & \text{if } x < 0 \\ x & \text{otherwise} \end{cases}"
+);
+latex_test!(
+    latex_factorial,
+    "(n + 1)! / n!",
+    r"\frac{\left(n + 1\right)!}{n!}"
+);
+latex_test!(latex_percent, "15% * x", r"15\% \cdot x");
+latex_test!(latex_remainder, "a % b", r"a \bmod b");
+latex_test!(
+    latex_floor_division,
+    "(a + 1) // b",
+    r"\left\lfloor \frac{a + 1}{b} \right\rfloor"
+);
+
+mathml_test!(mathml_number, "2.5", "<mn>2.5</mn>");
+mathml_test!(
+    mathml_fraction,
+    "a / b",
+    "<mfrac><mi>a</mi><mi>b</mi></mfrac>"
+);
+mathml_test!(mathml_power, "x ^ 2", "<msup><mi>x</mi><mn>2</mn></msup>");
+mathml_test!(mathml_sqrt, "sqrt(x)", "<msqrt><mi>x</mi></msqrt>");
+mathml_test!(
+    mathml_call,
+    "sin(x)",
+    "<mrow><mi>sin</mi><mo>&#x2061;</mo><mrow><mo>(</mo><mi>x</mi><mo>)</mo></mrow></mrow>"
+);
+mathml_test!(
+    mathml_precedence,
+    "(a + b) * c",
+    "<mrow><mrow><mo>(</mo><mrow><mi>a</mi><mo>+</mo><mi>b</mi></mrow><mo>)</mo></mrow>\
+     <mo>⋅</mo><mi>c</mi></mrow>"
+);
+mathml_test!(
+    mathml_unary,
+    "-x < pi",
+    "<mrow><mrow><mo>-</mo><mi>x</mi></mrow><mo>&lt;</mo><mi>π</mi></mrow>"
+);
+mathml_test!(
+    mathml_factorial,
+    "-n!",
+    "<mrow><mo>-</mo><mrow><mi>n</mi><mo>!</mo></mrow></mrow>"
+);
+mathml_test!(
+    mathml_floor_division,
+    "a // 2 mod b",
+    "<mrow><mrow><mo>⌊</mo><mfrac><mi>a</mi><mn>2</mn></mfrac><mo>⌋</mo></mrow>\
+     <mo>mod</mo><mi>b</mi></mrow>"
+);
+mathml_test!(
+    mathml_conditional,
+    "if x then 1 else 0",
+    "<mrow><mo>{</mo><mtable>\
+     <mtr><mtd><mn>1</mn></mtd><mtd><mtext>if&#xA0;</mtext><mi>x</mi></mtd></mtr>\
+     <mtr><mtd><mn>0</mn></mtd><mtd><mtext>otherwise</mtext></mtd></mtr>\
+     </mtable></mrow>"
+);
+
+#[test]
+fn computed_numbers() {
+    let negative = Expression::from(ExpressionKind::Number(-2.0));
+    assert_eq!(latex(&negative), "-2");
+    assert!(mathml(&negative).contains("<mrow><mo>-</mo><mn>2</mn></mrow>"));
+    let third = Expression::from(ExpressionKind::Number(Rational::new((-1).into(), 3.into())));
+    assert_eq!(latex(&third), r"-\frac{1}{3}");
+    assert!(mathml(&third).contains("<mrow><mo>-</mo><mfrac><mn>1</mn><mn>3</mn></mfrac></mrow>"));
+}
+
+#[test]
+fn statements() {
+    assert_eq!(
+        latex_statement(&statement("f(x, r) = x ^ r")).unwrap(),
+        r"f\left(x, r\right) = x^{r}"
+    );
+    assert_eq!(
+        latex_statement(&statement("area = pi * r ^ 2")).unwrap(),
+        r"\mathrm{area} = \pi \cdot r^{2}"
+    );
+    assert_eq!(
+        mathml_statement(&statement("y = 1")).unwrap(),
+        r#"<math xmlns="http://www.w3.org/1998/Math/MathML"><mrow><mi>y</mi><mo>=</mo><mn>1</mn></mrow></math>"#
+    );
+    assert_eq!(latex_statement::<f64>(&Statement::Empty), None);
+}
+
+#[test]
+fn export_format_names() {
+    for format in ExportFormat::ALL {
+        assert_eq!(format.to_string().parse(), Ok(format));
+    }
+    assert!("tex".parse::<ExportFormat>().is_err());
+}
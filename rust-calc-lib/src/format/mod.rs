@@ -0,0 +1,335 @@
+#[cfg(test)]
+mod tests;
+
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+use num_bigfloat::BigFloat;
+use num_complex::Complex64;
+
+use crate::numeric::integer::Integer;
+use crate::numeric::rational::Rational;
+
+/// Where the decimal point of a formatted number goes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Notation {
+    /// Fixed notation for moderately sized numbers, scientific notation for very large or very
+    /// small ones.
+    #[default]
+    Auto,
+    /// `1234.5`
+    Fixed,
+    /// `1.2345e3`
+    Scientific,
+    /// `1.2345e3` with the exponent a multiple of three, e.g. `12.345e3`
+    Engineering,
+}
+
+impl Notation {
+    pub const ALL: [Notation; 4] = [
+        Notation::Auto,
+        Notation::Fixed,
+        Notation::Scientific,
+        Notation::Engineering,
+    ];
+}
+
+impl Display for Notation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Notation::Auto => "auto",
+            Notation::Fixed => "fixed",
+            Notation::Scientific => "scientific",
+            Notation::Engineering => "engineering",
+        })
+    }
+}
+
+impl FromStr for Notation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        Notation::ALL
+            .into_iter()
+            .find(|notation| notation.to_string() == s)
+            .ok_or_else(|| {
+                let notations: Vec<_> = Notation::ALL.iter().map(Notation::to_string).collect();
+                format!(
+                    "unknown notation `{s}`, expected one of {}",
+                    notations.join(", ")
+                )
+            })
+    }
+}
+
+/// How numbers are turned into text for the user.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatOptions {
+    /// The number of significant digits to round to, or `None` for all the digits the value
+    /// carries.
+    pub precision: Option<usize>,
+    pub notation: Notation,
+    /// The character grouping the integer digits in threes, e.g. `,` for `1,234,567`.
+    pub thousands_separator: Option<char>,
+    /// Whether zeros at the end of the fractional part are dropped, `1.5` rather than `1.500`.
+    pub trim_zeros: bool,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            precision: None,
+            notation: Notation::Auto,
+            thousands_separator: None,
+            trim_zeros: true,
+        }
+    }
+}
+
+/// Formats a number for the user according to [`FormatOptions`].
+pub trait PrettyDisplay {
+    fn pretty(&self, options: &FormatOptions) -> String;
+}
+
+/// The smallest and largest decimal exponents [`Notation::Auto`] writes in fixed notation.
+const AUTO_FIXED_EXPONENTS: std::ops::RangeInclusive<i32> = -5..=20;
+
+/// A finite decimal number `d.ddd × 10^exponent` given by its significant digits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decimal {
+    negative: bool,
+    /// The significant digits, most significant first. Only zero has a leading zero.
+    digits: Vec<u8>,
+    exponent: i32,
+}
+
+impl Decimal {
+    /// Parses a number in the form `-1.2345e-6`, as written by `{:e}` for floats or by
+    /// [`BigFloat`]'s `Display`. The exponent is optional.
+    pub fn parse_scientific(text: &str) -> Option<Self> {
+        let (negative, text) = match text.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, text),
+        };
+        let (mantissa, exponent) = match text.split_once(['e', 'E']) {
+            Some((mantissa, exponent)) => (mantissa, exponent.parse::<i32>().ok()?),
+            None => (text, 0),
+        };
+        let (integer, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        if integer.is_empty()
+            || !(integer.chars().chain(fraction.chars())).all(|c| c.is_ascii_digit())
+        {
+            return None;
+        }
+        let digits: Vec<u8> = integer
+            .bytes()
+            .chain(fraction.bytes())
+            .map(|digit| digit - b'0')
+            .collect();
+        let exponent = exponent + integer.len() as i32 - 1;
+        Some(Self::normalized(negative, digits, exponent))
+    }
+
+    /// Strips leading zeros, adjusting the exponent so the value stays the same.
+    fn normalized(negative: bool, digits: Vec<u8>, exponent: i32) -> Self {
+        match digits.iter().position(|digit| *digit != 0) {
+            Some(leading_zeros) => Self {
+                negative,
+                digits: digits[leading_zeros..].to_vec(),
+                exponent: exponent - leading_zeros as i32,
+            },
+            None => Self {
+                negative: false,
+                digits: vec![0],
+                exponent: 0,
+            },
+        }
+    }
+
+    fn is_zero(&self) -> bool {
+        self.digits == [0]
+    }
+
+    /// Rounds half away from zero to at most `significant` digits.
+    fn round(&mut self, significant: usize) {
+        let significant = significant.max(1);
+        if self.digits.len() <= significant {
+            return;
+        }
+        let round_up = self.digits[significant] >= 5;
+        self.digits.truncate(significant);
+        if !round_up {
+            return;
+        }
+        for digit in self.digits.iter_mut().rev() {
+            if *digit < 9 {
+                *digit += 1;
+                return;
+            }
+            *digit = 0;
+        }
+        // every digit was a nine, 9.99 rounds to 10.0
+        self.digits.insert(0, 1);
+        self.digits.pop();
+        self.exponent += 1;
+    }
+
+    pub fn format(&self, options: &FormatOptions) -> String {
+        let mut decimal = self.clone();
+        if let Some(precision) = options.precision {
+            decimal.round(precision);
+            if !options.trim_zeros {
+                decimal.digits.resize(precision.max(1), 0);
+            }
+        }
+        if options.trim_zeros {
+            let significant = decimal
+                .digits
+                .iter()
+                .rposition(|digit| *digit != 0)
+                .map_or(1, |last| last + 1);
+            decimal.digits.truncate(significant);
+        }
+        let exponent = decimal.exponent;
+        let shift = match options.notation {
+            Notation::Fixed => 0,
+            Notation::Auto if decimal.is_zero() || AUTO_FIXED_EXPONENTS.contains(&exponent) => 0,
+            Notation::Auto | Notation::Scientific => exponent,
+            Notation::Engineering => exponent.div_euclid(3) * 3,
+        };
+        let mantissa = decimal.fixed(exponent - shift, options.thousands_separator);
+        if options.notation == Notation::Fixed || shift == 0 && options.notation == Notation::Auto {
+            mantissa
+        } else {
+            format!("{mantissa}e{shift}")
+        }
+    }
+
+    /// Writes the digits with the decimal point placed after the digit of magnitude `exponent`.
+    fn fixed(&self, exponent: i32, separator: Option<char>) -> String {
+        let digits: String = self
+            .digits
+            .iter()
+            .map(|digit| char::from(b'0' + digit))
+            .collect();
+        let (integer, fraction) = if exponent < 0 {
+            let zeros = "0".repeat((-exponent - 1) as usize);
+            (String::from("0"), format!("{zeros}{digits}"))
+        } else {
+            let integer_len = exponent as usize + 1;
+            if digits.len() <= integer_len {
+                (format!("{digits:0<integer_len$}"), String::new())
+            } else {
+                let (integer, fraction) = digits.split_at(integer_len);
+                (integer.to_owned(), fraction.to_owned())
+            }
+        };
+        let sign = if self.negative { "-" } else { "" };
+        let integer = match separator {
+            Some(separator) => group_thousands(&integer, separator),
+            None => integer,
+        };
+        if fraction.is_empty() {
+            format!("{sign}{integer}")
+        } else {
+            format!("{sign}{integer}.{fraction}")
+        }
+    }
+}
+
+/// Inserts `separator` between every group of three digits, counting from the right.
+fn group_thousands(digits: &str, separator: char) -> String {
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(separator);
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
+/// Formats a value with an optional leading minus sign and a string of decimal digits.
+fn format_integer(text: &str, options: &FormatOptions) -> String {
+    let (sign, digits) = match text.strip_prefix('-') {
+        Some(digits) => ("-", digits),
+        None => ("", text),
+    };
+    match options.thousands_separator {
+        Some(separator) => format!("{sign}{}", group_thousands(digits, separator)),
+        None => text.to_owned(),
+    }
+}
+
+impl PrettyDisplay for f64 {
+    fn pretty(&self, options: &FormatOptions) -> String {
+        if self.is_nan() {
+            return String::from("NaN");
+        }
+        if self.is_infinite() {
+            return String::from(if *self < 0.0 { "-inf" } else { "inf" });
+        }
+        // `{:e}` gives the shortest digits that read back as the same float
+        Decimal::parse_scientific(&format!("{self:e}"))
+            .expect("floats format as scientific notation")
+            .format(options)
+    }
+}
+
+impl PrettyDisplay for BigFloat {
+    fn pretty(&self, options: &FormatOptions) -> String {
+        if self.is_nan() {
+            return String::from("NaN");
+        }
+        if self.is_inf() {
+            return String::from(if self.is_inf_neg() { "-inf" } else { "inf" });
+        }
+        Decimal::parse_scientific(&self.to_string())
+            .expect("big floats format as scientific notation")
+            .format(options)
+    }
+}
+
+impl PrettyDisplay for Complex64 {
+    fn pretty(&self, options: &FormatOptions) -> String {
+        // drop rounding noise such as the 1.2e-16i in e^(i*pi), relative to the larger part
+        let scale = self.re.abs().max(self.im.abs());
+        let clean = |part: f64| {
+            if part.abs() <= scale * 1e-15 {
+                0.0
+            } else {
+                part
+            }
+        };
+        let (re, im) = (clean(self.re), clean(self.im));
+        if im == 0.0 {
+            re.pretty(options)
+        } else if re == 0.0 {
+            format!("{}i", im.pretty(options))
+        } else if im < 0.0 {
+            format!("{} - {}i", re.pretty(options), (-im).pretty(options))
+        } else {
+            format!("{} + {}i", re.pretty(options), im.pretty(options))
+        }
+    }
+}
+
+/// Exact values are never rounded, only the digit grouping applies.
+impl PrettyDisplay for Rational {
+    fn pretty(&self, options: &FormatOptions) -> String {
+        let numerator = format_integer(&self.numerator().to_string(), options);
+        if self.is_integer() {
+            numerator
+        } else {
+            let denominator = format_integer(&self.denominator().to_string(), options);
+            format!("{numerator}/{denominator}")
+        }
+    }
+}
+
+/// Exact values are never rounded, only the digit grouping applies.
+impl PrettyDisplay for Integer {
+    fn pretty(&self, options: &FormatOptions) -> String {
+        format_integer(&self.to_string(), options)
+    }
+}
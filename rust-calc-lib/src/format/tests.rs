@@ -0,0 +1,153 @@
+use num_bigfloat::BigFloat;
+use num_complex::Complex64;
+
+use crate::format::{Decimal, FormatOptions, Notation, PrettyDisplay};
+use crate::numeric::rational::Rational;
+
+fn options(precision: Option<usize>, notation: Notation) -> FormatOptions {
+    FormatOptions {
+        precision,
+        notation,
+        ..FormatOptions::default()
+    }
+}
+
+/// Macro to generate formatting tests of a decimal number written in scientific notation
+macro_rules! format_test {
+    ($name:ident, $input:expr, $options:expr, $expected:expr) => {
+        #[test]
+        fn $name() {
+            let formatted = Decimal::parse_scientific($input).unwrap().format(&$options);
+            assert_eq!(
+                formatted,
+                $expected,
+                "Format mismatch in test '{}'\ninput: {}",
+                stringify!($name),
+                $input,
+            );
+        }
+    };
+}
+
+format_test!(plain, "1.25e1", FormatOptions::default(), "12.5");
+format_test!(small_fixed, "1.5e-3", FormatOptions::default(), "0.0015");
+format_test!(
+    large_auto,
+    "6.02214076e23",
+    FormatOptions::default(),
+    "6.02214076e23"
+);
+format_test!(tiny_auto, "-1.6e-19", FormatOptions::default(), "-1.6e-19");
+format_test!(zero, "0.0", FormatOptions::default(), "0");
+format_test!(
+    rounding,
+    "3.14159265e0",
+    options(Some(4), Notation::Auto),
+    "3.142"
+);
+format_test!(
+    rounding_carries,
+    "9.9996e2",
+    options(Some(4), Notation::Auto),
+    "1000"
+);
+format_test!(
+    fixed_pads_integer_digits,
+    "1.23456e8",
+    options(Some(3), Notation::Fixed),
+    "123000000"
+);
+format_test!(
+    scientific,
+    "1.23456e4",
+    options(None, Notation::Scientific),
+    "1.23456e4"
+);
+format_test!(
+    scientific_small,
+    "-4.5e-7",
+    options(None, Notation::Scientific),
+    "-4.5e-7"
+);
+format_test!(
+    engineering,
+    "1.23456e4",
+    options(None, Notation::Engineering),
+    "12.3456e3"
+);
+format_test!(
+    engineering_negative_exponent,
+    "4.7e-8",
+    options(None, Notation::Engineering),
+    "47e-9"
+);
+format_test!(
+    keeps_trailing_zeros,
+    "1.5e0",
+    FormatOptions {
+        precision: Some(4),
+        trim_zeros: false,
+        ..FormatOptions::default()
+    },
+    "1.500"
+);
+format_test!(
+    thousands_separators,
+    "-1.2345678e6",
+    FormatOptions {
+        thousands_separator: Some(','),
+        ..FormatOptions::default()
+    },
+    "-1,234,567.8"
+);
+
+#[test]
+fn big_floats_keep_their_precision() {
+    let third = BigFloat::from_f64(1.0) / BigFloat::from_f64(3.0);
+    assert_eq!(
+        third.pretty(&FormatOptions::default()),
+        format!("0.{}", "3".repeat(40))
+    );
+    assert_eq!(third.pretty(&options(Some(5), Notation::Auto)), "0.33333");
+}
+
+#[test]
+fn special_floats() {
+    let options = FormatOptions::default();
+    assert_eq!(f64::NAN.pretty(&options), "NaN");
+    assert_eq!(f64::NEG_INFINITY.pretty(&options), "-inf");
+    assert_eq!(num_bigfloat::INF_POS.pretty(&options), "inf");
+}
+
+#[test]
+fn floats_use_shortest_digits() {
+    assert_eq!(
+        (0.1 + 0.2).pretty(&FormatOptions::default()),
+        "0.30000000000000004"
+    );
+    assert_eq!(
+        (0.1 + 0.2).pretty(&options(Some(15), Notation::Auto)),
+        "0.3"
+    );
+}
+
+#[test]
+fn complex_parts() {
+    let options = options(Some(3), Notation::Auto);
+    assert_eq!(
+        Complex64::new(1.0, -2.0 / 3.0).pretty(&options),
+        "1 - 0.667i"
+    );
+    assert_eq!(Complex64::new(-1.0, 1.2e-16).pretty(&options), "-1");
+}
+
+#[test]
+fn exact_values_are_not_rounded() {
+    let options = FormatOptions {
+        precision: Some(2),
+        thousands_separator: Some('_'),
+        ..FormatOptions::default()
+    };
+    let value = Rational::new(1234567.into(), 1000.into());
+    assert_eq!(value.pretty(&options), "1_234_567/1_000");
+}
@@ -1,5 +1,32 @@
+use std::fmt::{self, Display};
+
+use crate::span::Span;
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum LexerError {
     UnexpectedChar(char, usize),
+    /// The malformed literal and the position right after it.
     InvalidNumber(String, usize),
 }
+
+impl LexerError {
+    pub fn span(&self) -> Span {
+        match self {
+            LexerError::UnexpectedChar(c, position) => {
+                Span::new(*position, position + c.len_utf8())
+            }
+            LexerError::InvalidNumber(literal, end) => Span::new(end - literal.len(), *end),
+        }
+    }
+}
+
+impl Display for LexerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LexerError::UnexpectedChar(c, _) => write!(f, "unexpected character `{c}`"),
+            LexerError::InvalidNumber(literal, _) => write!(f, "invalid number `{literal}`"),
+        }
+    }
+}
+
+impl std::error::Error for LexerError {}
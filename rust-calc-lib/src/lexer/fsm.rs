@@ -1,13 +1,13 @@
 use std::marker::PhantomData;
 use std::str::Chars;
 
-use crate::lexer::token::{Operator, Punctuation};
-use crate::numeric::NumericValue;
+use crate::lexer::token::{Keyword, Literal, Operator, Punctuation};
+use crate::span::{Span, Spanned};
 
 use super::error::LexerError;
 use super::token::Token;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct FSMContext<'a> {
     input: Chars<'a>,
     current_char: Option<char>,
@@ -29,6 +29,16 @@ impl<'a> FSMContext<'a> {
         }
     }
 
+    /// Returns the character after the current one without consuming anything.
+    pub fn peek(&self) -> Option<char> {
+        self.input.clone().next()
+    }
+
+    /// Returns the character two after the current one without consuming anything.
+    pub fn peek_second(&self) -> Option<char> {
+        self.input.clone().nth(1)
+    }
+
     pub fn advance(&mut self) {
         if let Some(current) = self.current_char {
             self.position += current.len_utf8();
@@ -37,11 +47,11 @@ impl<'a> FSMContext<'a> {
     }
 }
 
-#[derive(Debug)]
-pub struct LexerFSM<'a, State, N: NumericValue> {
+#[derive(Debug, Clone)]
+pub struct LexerFSM<'a, State> {
     // ctx: &'a mut FSMContext<'a>,
     ctx: FSMContext<'a>,
-    _state: std::marker::PhantomData<(State, N)>,
+    _state: std::marker::PhantomData<State>,
 }
 
 // impl<'a, State> LexerFSM<'a, State> {
@@ -50,25 +60,105 @@ pub struct LexerFSM<'a, State, N: NumericValue> {
 //     }
 // }
 
-impl<'a, State, N: NumericValue> LexerFSM<'a, State, N> {
-    fn into_state<S>(self) -> LexerFSM<'a, S, N> {
-        LexerFSM::<S, N> {
+impl<'a, State> LexerFSM<'a, State> {
+    fn into_state<S>(self) -> LexerFSM<'a, S> {
+        LexerFSM::<S> {
             ctx: self.ctx,
             _state: PhantomData,
         }
     }
+
+    /// Builds a number token from the literal in the buffer, consuming an imaginary unit suffix
+    /// (`4i`) if one follows.
+    fn number_token(&mut self, radix: u32) -> Result<Token, LexerError> {
+        let text = self.ctx.buffer.replace('_', "");
+        let (digits, exponent) = match radix {
+            10 => text.split_once(['e', 'E']).unwrap_or((&text, "0")),
+            _ => (&text[2..], "0"),
+        };
+        let exponent = exponent.parse().map_err(|_| self.invalid_number())?;
+        let mut literal = Literal {
+            digits: digits.to_owned(),
+            radix,
+            exponent,
+            imaginary: false,
+        };
+        if self.ctx.current_char == Some('i') && !self.ctx.peek().is_some_and(is_identifier_char) {
+            self.ctx.buffer.push('i');
+            self.ctx.advance();
+            literal.imaginary = true;
+        }
+        Ok(Token::Number(literal))
+    }
+
+    /// An error for the literal read so far.
+    fn invalid_number(&self) -> LexerError {
+        LexerError::InvalidNumber(self.ctx.buffer.clone(), self.ctx.position)
+    }
+
+    /// Reads a run of digits, as told apart by `is_digit`, that may be grouped by single `_`
+    /// separators between them, like `1_000_000`.
+    fn push_digits(&mut self, is_digit: impl Fn(char) -> bool) -> Result<(), LexerError> {
+        let start = self.ctx.buffer.len();
+        while let Some(c) = self.ctx.current_char.filter(|c| *c == '_' || is_digit(*c)) {
+            self.ctx.buffer.push(c);
+            self.ctx.advance();
+        }
+        let run = &self.ctx.buffer[start..];
+        if run.starts_with('_') || run.ends_with('_') || run.contains("__") {
+            return Err(self.invalid_number());
+        }
+        Ok(())
+    }
+
+    /// Whether an exponent starts at the current character, which is an `e` followed by digits
+    /// that may be signed. Otherwise the `e` starts a name, as in `2e` or `4end`.
+    fn at_exponent(&self) -> bool {
+        if !matches!(self.ctx.current_char, Some('e' | 'E')) {
+            return false;
+        }
+        match self.ctx.peek() {
+            Some('+' | '-') => self.ctx.peek_second().is_some_and(|c| c.is_ascii_digit()),
+            next => next.is_some_and(|c| c.is_ascii_digit()),
+        }
+    }
 }
 
-#[derive(Debug)]
+fn is_identifier_char(c: char) -> bool {
+    c.is_ascii_alphabetic() || c.is_ascii_digit() || c == '_'
+}
+
+/// The radix of a literal whose prefix is `0` followed by `c`.
+fn radix_of_prefix(c: char) -> Option<u32> {
+    match c {
+        'x' | 'X' => Some(16),
+        'o' | 'O' => Some(8),
+        'b' | 'B' => Some(2),
+        _ => None,
+    }
+}
+
+/// The token read from the input and the machine ready to read the one after it.
+type NextToken<'a> = Result<(Spanned<Token>, LexerFSM<'a, Start>), LexerError>;
+
+#[derive(Debug, Clone)]
 pub struct Start;
 #[derive(Debug)]
 pub struct IntegerPart;
 #[derive(Debug)]
 pub struct DecimalPart;
 #[derive(Debug)]
+pub struct ExponentPart;
+#[derive(Debug)]
+pub struct RadixDigits;
+#[derive(Debug)]
 pub struct InIdentifier;
+#[derive(Debug)]
+pub struct InOperator;
+#[derive(Debug)]
+pub struct InResultRef;
 
-impl<'a, N: NumericValue> LexerFSM<'a, Start, N> {
+impl<'a> LexerFSM<'a, Start> {
     pub fn new(input: &'a str) -> Self {
         Self {
             ctx: FSMContext::new(input),
@@ -76,92 +166,189 @@ impl<'a, N: NumericValue> LexerFSM<'a, Start, N> {
         }
     }
 
-    pub fn next_token(mut self) -> Result<(Token<N>, LexerFSM<'a, Start, N>), LexerError> {
+    pub fn next_token(mut self) -> NextToken<'a> {
         while let Some(c) = self.ctx.current_char {
             if c.is_whitespace() {
                 self.ctx.advance();
                 continue;
             }
-            if c.is_ascii_digit() {
-                return self
-                    .into_state::<IntegerPart>()
-                    .collect()
-                    .map(|(token, fsm)| (token, fsm.into_state()));
-            }
-            if c.is_ascii_alphabetic() {
-                let (token, fsm) = self.into_state::<InIdentifier>().collect();
-                return Ok((token, fsm.into_state()));
-            }
-            if let Some(op) = Operator::get(c) {
-                self.ctx.advance();
-                return Ok((Token::Operator(op), self));
-            }
-            if let Some(punc) = Punctuation::get(c) {
-                self.ctx.advance();
-                return Ok((Token::Punctuation(punc), self));
+            if c == '#' {
+                self.skip_comment();
+                continue;
             }
-            return Err(LexerError::UnexpectedChar(c, self.ctx.position));
+            let start = self.ctx.position;
+            let (token, fsm) = self.collect_token(c)?;
+            let span = Span::new(start, fsm.ctx.position);
+            return Ok((Spanned::new(token, span), fsm));
         }
-        Ok((Token::Eof, self))
+        let end = Span::new(self.ctx.position, self.ctx.position);
+        Ok((Spanned::new(Token::Eof, end), self))
+    }
+
+    /// Skips a `#` comment up to the end of the line.
+    fn skip_comment(&mut self) {
+        while self.ctx.current_char.is_some_and(|c| c != '\n') {
+            self.ctx.advance();
+        }
+    }
+
+    fn collect_token(self, c: char) -> Result<(Token, LexerFSM<'a, Start>), LexerError> {
+        if c.is_ascii_digit() {
+            return self
+                .into_state::<IntegerPart>()
+                .collect()
+                .map(|(token, fsm)| (token, fsm.into_state()));
+        }
+        if c.is_ascii_alphabetic() {
+            let (token, fsm) = self.into_state::<InIdentifier>().collect();
+            return Ok((token, fsm.into_state()));
+        }
+        if c == '$' {
+            return self
+                .into_state::<InResultRef>()
+                .collect()
+                .map(|(token, fsm)| (token, fsm.into_state()));
+        }
+        self.into_state::<InOperator>()
+            .collect(c)
+            .map(|(token, fsm)| (token, fsm.into_state()))
     }
 }
 
-impl<'a, N: NumericValue> LexerFSM<'a, IntegerPart, N> {
-    pub fn collect(mut self) -> Result<(Token<N>, LexerFSM<'a, IntegerPart, N>), LexerError> {
+impl<'a> LexerFSM<'a, IntegerPart> {
+    pub fn collect(mut self) -> Result<(Token, LexerFSM<'a, IntegerPart>), LexerError> {
         self.ctx.buffer.clear();
-        while let Some(c) = self.ctx.current_char {
-            if c.is_ascii_digit() {
-                self.ctx.buffer.push(c);
-                self.ctx.advance();
-                continue;
-            }
-            if c == '.' {
-                self.ctx.buffer.push(c);
-                self.ctx.advance(); // consume the decimal separator (a dot - '.')
-                return self
-                    .into_state::<DecimalPart>()
-                    .collect()
-                    .map(|(token, fsm)| (token, fsm.into_state()));
-            }
-            break;
+        if self.ctx.current_char == Some('0')
+            && let Some(radix) = self.ctx.peek().and_then(radix_of_prefix)
+        {
+            return self
+                .into_state::<RadixDigits>()
+                .collect(radix)
+                .map(|(token, fsm)| (token, fsm.into_state()));
+        }
+        self.push_digits(|c| c.is_ascii_digit())?;
+        if self.ctx.current_char == Some('.') {
+            self.ctx.buffer.push('.');
+            self.ctx.advance(); // consume the decimal separator (a dot - '.')
+            return self
+                .into_state::<DecimalPart>()
+                .collect()
+                .map(|(token, fsm)| (token, fsm.into_state()));
+        }
+        if self.at_exponent() {
+            return self
+                .into_state::<ExponentPart>()
+                .collect()
+                .map(|(token, fsm)| (token, fsm.into_state()));
+        }
+        Ok((self.number_token(10)?, self))
+    }
+}
+
+impl<'a> LexerFSM<'a, DecimalPart> {
+    pub fn collect(mut self) -> Result<(Token, LexerFSM<'a, DecimalPart>), LexerError> {
+        self.push_digits(|c| c.is_ascii_digit())?;
+        if self.at_exponent() {
+            return self
+                .into_state::<ExponentPart>()
+                .collect()
+                .map(|(token, fsm)| (token, fsm.into_state()));
+        }
+        Ok((self.number_token(10)?, self))
+    }
+}
+
+impl<'a> LexerFSM<'a, ExponentPart> {
+    /// Reads the exponent of a literal such as `6.02e23` or `1E-9`, starting at the `e`.
+    pub fn collect(mut self) -> Result<(Token, LexerFSM<'a, ExponentPart>), LexerError> {
+        self.ctx.buffer.extend(self.ctx.current_char);
+        self.ctx.advance(); // consume the `e`
+        if let Some(sign @ ('+' | '-')) = self.ctx.current_char {
+            self.ctx.buffer.push(sign);
+            self.ctx.advance();
+        }
+        self.push_digits(|c| c.is_ascii_digit())?;
+        Ok((self.number_token(10)?, self))
+    }
+}
+
+impl<'a> LexerFSM<'a, RadixDigits> {
+    /// Reads a literal with a radix prefix such as `0xFF`, `0b1010` or `0o17`, starting at the
+    /// leading zero.
+    pub fn collect(mut self, radix: u32) -> Result<(Token, LexerFSM<'a, RadixDigits>), LexerError> {
+        for _ in 0..2 {
+            self.ctx.buffer.extend(self.ctx.current_char);
+            self.ctx.advance();
         }
-        Ok((
-            Token::Number(N::from_str_radix(&self.ctx.buffer, 10).map_err(|_| {
-                LexerError::InvalidNumber(self.ctx.buffer.clone(), self.ctx.position)
-            })?),
-            self,
-        ))
+        // letters are read too, so that `0b12` or `0xG` are reported as a whole
+        self.push_digits(is_identifier_char)?;
+        let digits = &self.ctx.buffer[2..];
+        if digits.is_empty() || !digits.chars().all(|c| c == '_' || c.is_digit(radix)) {
+            return Err(self.invalid_number());
+        }
+        Ok((self.number_token(radix)?, self))
     }
 }
 
-impl<'a, N: NumericValue> LexerFSM<'a, DecimalPart, N> {
-    pub fn collect(mut self) -> Result<(Token<N>, LexerFSM<'a, DecimalPart, N>), LexerError> {
+impl<'a> LexerFSM<'a, InIdentifier> {
+    pub fn collect(mut self) -> (Token, LexerFSM<'a, InIdentifier>) {
+        self.ctx.buffer.clear();
         while let Some(c) = self.ctx.current_char {
-            if !c.is_ascii_digit() {
+            if !is_identifier_char(c) {
                 break;
             }
             self.ctx.buffer.push(c);
             self.ctx.advance();
         }
-        Ok((
-            Token::Number(N::from_str_radix(&self.ctx.buffer, 10).map_err(|_| {
-                LexerError::InvalidNumber(self.ctx.buffer.clone(), self.ctx.position)
-            })?),
-            self,
-        ))
+        let token = if let Some(keyword) = Keyword::get(&self.ctx.buffer) {
+            Token::Keyword(keyword)
+        } else if let Some(op) = Operator::get_word(&self.ctx.buffer) {
+            Token::Operator(op)
+        } else {
+            Token::Identifier(self.ctx.buffer.clone())
+        };
+        (token, self)
+    }
+}
+
+impl<'a> LexerFSM<'a, InOperator> {
+    /// Reads an operator or a punctuation symbol starting with `first`, preferring a two character
+    /// operator (`<=`) over a one character one (`<`).
+    pub fn collect(mut self, first: char) -> Result<(Token, LexerFSM<'a, InOperator>), LexerError> {
+        let position = self.ctx.position;
+        self.ctx.advance();
+        if let Some(second) = self.ctx.current_char
+            && let Some(op) = Operator::get_pair(first, second)
+        {
+            self.ctx.advance();
+            return Ok((Token::Operator(op), self));
+        }
+        if let Some(op) = Operator::get(first) {
+            return Ok((Token::Operator(op), self));
+        }
+        if let Some(punc) = Punctuation::get(first) {
+            return Ok((Token::Punctuation(punc), self));
+        }
+        Err(LexerError::UnexpectedChar(first, position))
     }
 }
 
-impl<'a, N: NumericValue> LexerFSM<'a, InIdentifier, N> {
-    pub fn collect(mut self) -> (Token<N>, LexerFSM<'a, InIdentifier, N>) {
+impl<'a> LexerFSM<'a, InResultRef> {
+    /// Reads a result reference such as `$12`, starting at the dollar sign.
+    pub fn collect(mut self) -> Result<(Token, LexerFSM<'a, InResultRef>), LexerError> {
+        let position = self.ctx.position;
+        self.ctx.advance();
         self.ctx.buffer.clear();
         while let Some(c) = self.ctx.current_char {
-            if !c.is_ascii_alphabetic() && !c.is_ascii_digit() && c != '_' {
+            if !c.is_ascii_digit() {
                 break;
             }
             self.ctx.buffer.push(c);
             self.ctx.advance();
         }
-        (Token::Identifier(self.ctx.buffer.clone()), self)
+        match self.ctx.buffer.parse() {
+            Ok(number) => Ok((Token::ResultRef(number), self)),
+            Err(_) => Err(LexerError::UnexpectedChar('$', position)),
+        }
     }
 }
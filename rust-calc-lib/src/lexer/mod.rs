@@ -8,14 +8,14 @@ use error::LexerError;
 use token::Token;
 
 use crate::lexer::fsm::{LexerFSM, Start};
-use crate::numeric::NumericValue;
+use crate::span::Spanned;
 
-#[derive(Debug)]
-pub struct Lexer<'a, N: NumericValue> {
-    fsm: Option<LexerFSM<'a, Start, N>>,
+#[derive(Debug, Clone)]
+pub struct Lexer<'a> {
+    fsm: Option<LexerFSM<'a, Start>>,
 }
 
-impl<'a, N: NumericValue> Lexer<'a, N> {
+impl<'a> Lexer<'a> {
     pub fn new(input: &'a str) -> Self {
         Self {
             fsm: Some(LexerFSM::new(input)),
@@ -23,14 +23,14 @@ impl<'a, N: NumericValue> Lexer<'a, N> {
     }
 }
 
-impl<'a, N: NumericValue> Iterator for Lexer<'a, N> {
-    type Item = Result<Token<N>, LexerError>;
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Result<Spanned<Token>, LexerError>;
 
     fn next(&mut self) -> Option<Self::Item> {
         let fsm = self.fsm.take()?;
         match fsm.next_token() {
             Ok((token, new_fsm)) => {
-                if token == Token::Eof {
+                if token.node == Token::Eof {
                     return None;
                 }
                 self.fsm = Some(new_fsm);
@@ -1,15 +1,31 @@
 use crate::lexer::{
+    Lexer,
     error::LexerError,
-    token::{Operator, Punctuation, Token},
+    token::{Keyword, Literal, Operator, Punctuation, Token},
 };
+use crate::span::Span;
+
+/// A number token of a decimal literal without exponent
+fn number(digits: &str) -> Token {
+    Token::Number(Literal::decimal(digits))
+}
+
+fn literal(digits: &str, radix: u32, exponent: i64) -> Token {
+    Token::Number(Literal {
+        digits: digits.into(),
+        radix,
+        exponent,
+        imaginary: false,
+    })
+}
 
 /// Macro to generate lexer tests
 macro_rules! lexer_test {
     ($name:ident, $input:expr, [$($expected:expr),* $(,)?]) => {
         #[test]
         fn $name() {
-            let lexer = crate::lexer::Lexer::<f64>::new($input);
-            let tokens: Vec<_> = lexer.collect();
+            let lexer = crate::lexer::Lexer::new($input);
+            let tokens: Vec<_> = lexer.map(|token| token.map(|token| token.node)).collect();
 
             let expected_tokens = vec![
                 $($expected,)*
@@ -34,13 +50,112 @@ macro_rules! lexer_test {
 lexer_test!(
     numbers,
     "42 6.954 0.001",
+    [Ok(number("42")), Ok(number("6.954")), Ok(number("0.001"))]
+);
+
+// Scientific notation
+lexer_test!(
+    scientific_numbers,
+    "6.02e23 1E-9 2.5e+3 1.e2",
+    [
+        Ok(literal("6.02", 10, 23)),
+        Ok(literal("1", 10, -9)),
+        Ok(literal("2.5", 10, 3)),
+        Ok(literal("1.", 10, 2)),
+    ]
+);
+
+lexer_test!(
+    e_without_digits_is_a_name,
+    "2e 4end 3e+x",
+    [
+        Ok(number("2")),
+        Ok(Token::Identifier("e".into())),
+        Ok(number("4")),
+        Ok(Token::Identifier("end".into())),
+        Ok(number("3")),
+        Ok(Token::Identifier("e".into())),
+        Ok(Token::Operator(Operator::Plus)),
+        Ok(Token::Identifier("x".into())),
+    ]
+);
+
+lexer_test!(
+    exponent_out_of_range,
+    "1e99999999999999999999",
+    [Err(LexerError::InvalidNumber(
+        "1e99999999999999999999".into(),
+        22
+    ))]
+);
+
+// Radix prefixes
+lexer_test!(
+    radix_literals,
+    "0xFF 0Xa0 0b1010 0o17 0",
     [
-        Ok(Token::Number(42.0)),
-        Ok(Token::Number(6.954)),
-        Ok(Token::Number(0.001))
+        Ok(literal("FF", 16, 0)),
+        Ok(literal("a0", 16, 0)),
+        Ok(literal("1010", 2, 0)),
+        Ok(literal("17", 8, 0)),
+        Ok(number("0")),
     ]
 );
 
+lexer_test!(
+    invalid_binary_digit,
+    "0b102 + 1",
+    [Err(LexerError::InvalidNumber("0b102".into(), 5))]
+);
+
+lexer_test!(
+    invalid_hex_digit,
+    "0xFG",
+    [Err(LexerError::InvalidNumber("0xFG".into(), 4))]
+);
+
+lexer_test!(
+    radix_prefix_without_digits,
+    "0x;",
+    [Err(LexerError::InvalidNumber("0x".into(), 2))]
+);
+
+// Digit separators
+lexer_test!(
+    digit_separators,
+    "1_000_000 0.000_1 1_0e1_0 0xFF_FF",
+    [
+        Ok(number("1000000")),
+        Ok(number("0.0001")),
+        Ok(literal("10", 10, 10)),
+        Ok(literal("FFFF", 16, 0)),
+    ]
+);
+
+lexer_test!(
+    doubled_separator,
+    "1__0",
+    [Err(LexerError::InvalidNumber("1__0".into(), 4))]
+);
+
+lexer_test!(
+    trailing_separator,
+    "1_ + 2",
+    [Err(LexerError::InvalidNumber("1_".into(), 2))]
+);
+
+lexer_test!(
+    separator_after_decimal_point,
+    "1._5",
+    [Err(LexerError::InvalidNumber("1._5".into(), 4))]
+);
+
+lexer_test!(
+    separator_after_radix_prefix,
+    "0x_FF",
+    [Err(LexerError::InvalidNumber("0x_FF".into(), 5))]
+);
+
 // Operators
 lexer_test!(
     operators,
@@ -53,15 +168,31 @@ lexer_test!(
     ]
 );
 
+lexer_test!(
+    remainder_operators,
+    "7 % 3 mod 2 // 1!",
+    [
+        Ok(number("7")),
+        Ok(Token::Operator(Operator::Percent)),
+        Ok(number("3")),
+        Ok(Token::Operator(Operator::Rem)),
+        Ok(number("2")),
+        Ok(Token::Operator(Operator::FloorDiv)),
+        Ok(number("1")),
+        Ok(Token::Operator(Operator::Not)),
+    ]
+);
+
 // Punctuation
 lexer_test!(
     punctuation,
-    "( );  =",
+    "( );  = ,",
     [
         Ok(Token::Punctuation(Punctuation::LeftParenthesis)),
         Ok(Token::Punctuation(Punctuation::RightParenthesis)),
         Ok(Token::Punctuation(Punctuation::Semicolon)),
-        Ok(Token::Punctuation(Punctuation::Assignment))
+        Ok(Token::Punctuation(Punctuation::Assignment)),
+        Ok(Token::Punctuation(Punctuation::Comma))
     ]
 );
 
@@ -83,14 +214,14 @@ lexer_test!(
     [
         Ok(Token::Identifier("x".into())),
         Ok(Token::Punctuation(Punctuation::Assignment)),
-        Ok(Token::Number(3.0)),
+        Ok(number("3")),
         Ok(Token::Operator(Operator::Plus)),
-        Ok(Token::Number(4.5)),
+        Ok(number("4.5")),
         Ok(Token::Operator(Operator::Star)),
         Ok(Token::Punctuation(Punctuation::LeftParenthesis)),
         Ok(Token::Identifier("y".into())),
         Ok(Token::Operator(Operator::Minus)),
-        Ok(Token::Number(2.0)),
+        Ok(number("2")),
         Ok(Token::Punctuation(Punctuation::RightParenthesis)),
         Ok(Token::Punctuation(Punctuation::Semicolon))
     ]
@@ -100,8 +231,147 @@ lexer_test!(
 lexer_test!(
     invalid_character,
     "42 &",
+    [Ok(number("42")), Err(LexerError::UnexpectedChar('&', 3))]
+);
+
+#[test]
+fn token_spans() {
+    let spans: Vec<_> = Lexer::new("x1 = 4.25 *(y)")
+        .map(|token| token.unwrap().span)
+        .collect();
+    assert_eq!(
+        spans,
+        vec![
+            Span::new(0, 2),
+            Span::new(3, 4),
+            Span::new(5, 9),
+            Span::new(10, 11),
+            Span::new(11, 12),
+            Span::new(12, 13),
+            Span::new(13, 14),
+        ]
+    );
+}
+
+#[test]
+fn error_spans() {
+    assert_eq!(LexerError::UnexpectedChar('&', 3).span(), Span::new(3, 4));
+    assert_eq!(
+        LexerError::InvalidNumber("1.".into(), 5).span(),
+        Span::new(3, 5)
+    );
+}
+
+// Comparison and logical operators
+lexer_test!(
+    two_character_operators,
+    "a == b != c <= d >= e && f || !g < h > i = j",
+    [
+        Ok(Token::Identifier("a".into())),
+        Ok(Token::Operator(Operator::Equal)),
+        Ok(Token::Identifier("b".into())),
+        Ok(Token::Operator(Operator::NotEqual)),
+        Ok(Token::Identifier("c".into())),
+        Ok(Token::Operator(Operator::LessEqual)),
+        Ok(Token::Identifier("d".into())),
+        Ok(Token::Operator(Operator::GreaterEqual)),
+        Ok(Token::Identifier("e".into())),
+        Ok(Token::Operator(Operator::And)),
+        Ok(Token::Identifier("f".into())),
+        Ok(Token::Operator(Operator::Or)),
+        Ok(Token::Operator(Operator::Not)),
+        Ok(Token::Identifier("g".into())),
+        Ok(Token::Operator(Operator::Less)),
+        Ok(Token::Identifier("h".into())),
+        Ok(Token::Operator(Operator::Greater)),
+        Ok(Token::Identifier("i".into())),
+        Ok(Token::Punctuation(Punctuation::Assignment)),
+        Ok(Token::Identifier("j".into())),
+    ]
+);
+
+lexer_test!(
+    keywords,
+    "if x then y else z",
+    [
+        Ok(Token::Keyword(Keyword::If)),
+        Ok(Token::Identifier("x".into())),
+        Ok(Token::Keyword(Keyword::Then)),
+        Ok(Token::Identifier("y".into())),
+        Ok(Token::Keyword(Keyword::Else)),
+        Ok(Token::Identifier("z".into())),
+    ]
+);
+
+lexer_test!(
+    lone_ampersand,
+    "1 & 2",
+    [Ok(number("1")), Err(LexerError::UnexpectedChar('&', 2))]
+);
+
+// Imaginary literals
+lexer_test!(
+    imaginary_suffix,
+    "4i 2.5e1i",
     [
-        Ok(Token::Number(42.0)),
-        Err(LexerError::UnexpectedChar('&', 3))
+        Ok(Token::Number(Literal {
+            imaginary: true,
+            ..Literal::decimal("4")
+        })),
+        Ok(Token::Number(Literal {
+            digits: "2.5".into(),
+            radix: 10,
+            exponent: 1,
+            imaginary: true,
+        })),
     ]
 );
+
+lexer_test!(
+    identifier_after_number,
+    "4in",
+    [Ok(number("4")), Ok(Token::Identifier("in".into())),]
+);
+
+#[test]
+fn literal_display() {
+    let literals = ["42", "6.02e23", "1e-9", "0xFF", "0b1010", "0o17", "2.5i"];
+    for input in literals {
+        match Lexer::new(input).next() {
+            Some(Ok(token)) => assert_eq!(token.node.to_string(), format!("number `{input}`")),
+            token => panic!("`{input}` is not a number: {token:?}"),
+        }
+    }
+}
+
+// Comments
+lexer_test!(
+    comments_run_to_end_of_line,
+    "x = 1; # the answer is 2 * x\n# a whole line\ny",
+    [
+        Ok(Token::Identifier("x".into())),
+        Ok(Token::Punctuation(Punctuation::Assignment)),
+        Ok(number("1")),
+        Ok(Token::Punctuation(Punctuation::Semicolon)),
+        Ok(Token::Identifier("y".into())),
+    ]
+);
+
+lexer_test!(comment_only, "# nothing here", []);
+
+// Result references
+lexer_test!(
+    result_references,
+    "$1 + $23",
+    [
+        Ok(Token::ResultRef(1)),
+        Ok(Token::Operator(Operator::Plus)),
+        Ok(Token::ResultRef(23)),
+    ]
+);
+
+lexer_test!(
+    dollar_needs_a_number,
+    "$x",
+    [Err(LexerError::UnexpectedChar('$', 0))]
+);
@@ -1,4 +1,7 @@
-use crate::numeric::NumericValue;
+use std::cmp::Ordering;
+use std::fmt::{self, Display};
+
+use crate::numeric::{NumericValue, from_bool};
 
 #[derive(Debug, Clone, PartialEq, Copy)]
 pub enum Associativity {
@@ -12,7 +15,24 @@ pub enum Operator {
     Minus,
     Star,
     Slash,
+    /// Floored integer division `//`, rounding the quotient towards negative infinity.
+    FloorDiv,
+    /// The remainder `mod` of floored division, with the sign of the divisor.
+    Rem,
     Caret,
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    And,
+    Or,
+    /// Logical negation before an operand and factorial after one, only valid as a unary
+    /// operator.
+    Not,
+    /// `%`, a percentage after an operand. Between two operands it stands for [`Operator::Rem`].
+    Percent,
 }
 
 impl Operator {
@@ -23,30 +43,73 @@ impl Operator {
             '*' => Some(Self::Star),
             '/' => Some(Self::Slash),
             '^' => Some(Self::Caret),
+            '<' => Some(Self::Less),
+            '>' => Some(Self::Greater),
+            '!' => Some(Self::Not),
+            '%' => Some(Self::Percent),
+            _ => None,
+        }
+    }
+
+    /// Returns the two character operator spelled `first` followed by `second`, if there is one.
+    pub fn get_pair(first: char, second: char) -> Option<Self> {
+        match (first, second) {
+            ('=', '=') => Some(Self::Equal),
+            ('!', '=') => Some(Self::NotEqual),
+            ('<', '=') => Some(Self::LessEqual),
+            ('>', '=') => Some(Self::GreaterEqual),
+            ('&', '&') => Some(Self::And),
+            ('|', '|') => Some(Self::Or),
+            ('/', '/') => Some(Self::FloorDiv),
+            _ => None,
+        }
+    }
+
+    /// Returns the operator spelled as the word `word`, if there is one.
+    pub fn get_word(word: &str) -> Option<Self> {
+        match word {
+            "mod" => Some(Self::Rem),
             _ => None,
         }
     }
 
     pub fn priority(&self) -> u8 {
         match self {
-            Operator::Plus => 1,
-            Operator::Minus => 1,
-            Operator::Star => 2,
-            Operator::Slash => 2,
-            Operator::Caret => 3,
+            Operator::Or => 1,
+            Operator::And => 2,
+            Operator::Equal => 3,
+            Operator::NotEqual => 3,
+            Operator::Less => 4,
+            Operator::LessEqual => 4,
+            Operator::Greater => 4,
+            Operator::GreaterEqual => 4,
+            Operator::Plus => 5,
+            Operator::Minus => 5,
+            Operator::Star => 6,
+            Operator::Slash => 6,
+            Operator::FloorDiv => 6,
+            Operator::Rem => 6,
+            Operator::Percent => 6,
+            Operator::Caret => 7,
+            Operator::Not => 8,
         }
     }
 
     pub fn associativity(&self) -> Associativity {
         match self {
-            Operator::Plus => Associativity::Left,
-            Operator::Minus => Associativity::Left,
-            Operator::Star => Associativity::Left,
-            Operator::Slash => Associativity::Left,
             Operator::Caret => Associativity::Right,
+            Operator::Not => Associativity::Right,
+            _ => Associativity::Left,
         }
     }
 
+    /// Whether the operator can stand between two operands.
+    pub fn is_binary(&self) -> bool {
+        !matches!(self, Operator::Not | Operator::Percent)
+    }
+
+    /// Applies a binary operator. Comparisons and logical operators yield one for true and zero
+    /// for false, and treat any non-zero operand as true.
     pub fn apply<N: NumericValue>(&self, a: N, b: N) -> Result<N, String> {
         match self {
             Operator::Plus => Ok(a + b),
@@ -59,17 +122,111 @@ impl Operator {
                     Ok(a / b)
                 }
             }
-            Operator::Caret => Ok(a.pow(b)),
+            Operator::FloorDiv => floored_division(a, b).map(|(quotient, _)| quotient),
+            Operator::Rem => floored_division(a, b).map(|(_, remainder)| remainder),
+            Operator::Caret => a.checked_pow(b),
+            Operator::Equal => Ok(from_bool(a == b)),
+            Operator::NotEqual => Ok(from_bool(a != b)),
+            Operator::Less => compare(&a, &b, Ordering::is_lt),
+            Operator::LessEqual => compare(&a, &b, Ordering::is_le),
+            Operator::Greater => compare(&a, &b, Ordering::is_gt),
+            Operator::GreaterEqual => compare(&a, &b, Ordering::is_ge),
+            Operator::And => Ok(from_bool(a != N::zero() && b != N::zero())),
+            Operator::Or => Ok(from_bool(a != N::zero() || b != N::zero())),
+            Operator::Not | Operator::Percent => Err(format!("`{self}` is not a binary operator")),
         }
     }
 }
 
+/// Tests the order of `a` and `b`. Comparisons with an unordered value such as NaN are false,
+/// values off the real line cannot be compared at all.
+fn compare<N: NumericValue>(a: &N, b: &N, test: fn(Ordering) -> bool) -> Result<N, String> {
+    if !a.is_real() || !b.is_real() {
+        return Err("complex numbers cannot be ordered".to_owned());
+    }
+    Ok(from_bool(a.compare(b).is_some_and(test)))
+}
+
+/// Divides `a` by `b`, returning the quotient rounded towards negative infinity and the
+/// remainder, which has the sign of `b`: `-7 // 2` is `-4` and `-7 mod 2` is `1`.
+fn floored_division<N: NumericValue>(a: N, b: N) -> Result<(N, N), String> {
+    if !a.is_real() || !b.is_real() {
+        return Err("complex numbers cannot be divided with a remainder".to_owned());
+    }
+    if b == N::zero() {
+        return Err("Division by zero".to_owned());
+    }
+    let mut remainder = a.clone() % b.clone();
+    let mut quotient = (a - remainder.clone()) / b.clone();
+    let is_negative = |value: &N| value.compare(&N::zero()) == Some(Ordering::Less);
+    if remainder != N::zero() && is_negative(&remainder) != is_negative(&b) {
+        remainder = remainder + b;
+        quotient = quotient - N::one();
+    }
+    Ok((quotient, remainder))
+}
+
+impl Display for Operator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            Operator::Plus => "+",
+            Operator::Minus => "-",
+            Operator::Star => "*",
+            Operator::Slash => "/",
+            Operator::FloorDiv => "//",
+            Operator::Rem => "mod",
+            Operator::Caret => "^",
+            Operator::Equal => "==",
+            Operator::NotEqual => "!=",
+            Operator::Less => "<",
+            Operator::LessEqual => "<=",
+            Operator::Greater => ">",
+            Operator::GreaterEqual => ">=",
+            Operator::And => "&&",
+            Operator::Or => "||",
+            Operator::Not => "!",
+            Operator::Percent => "%",
+        };
+        f.write_str(symbol)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Copy)]
+pub enum Keyword {
+    If,
+    Then,
+    Else,
+}
+
+impl Keyword {
+    pub fn get(word: &str) -> Option<Self> {
+        match word {
+            "if" => Some(Self::If),
+            "then" => Some(Self::Then),
+            "else" => Some(Self::Else),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Keyword {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let word = match self {
+            Keyword::If => "if",
+            Keyword::Then => "then",
+            Keyword::Else => "else",
+        };
+        f.write_str(word)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Copy)]
 pub enum Punctuation {
     LeftParenthesis,
     RightParenthesis,
     Semicolon,
     Assignment,
+    Comma,
 }
 
 impl Punctuation {
@@ -79,16 +236,93 @@ impl Punctuation {
             ')' => Some(Self::RightParenthesis),
             ';' => Some(Self::Semicolon),
             '=' => Some(Self::Assignment),
+            ',' => Some(Self::Comma),
             _ => None,
         }
     }
 }
 
+impl Display for Punctuation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            Punctuation::LeftParenthesis => "(",
+            Punctuation::RightParenthesis => ")",
+            Punctuation::Semicolon => ";",
+            Punctuation::Assignment => "=",
+            Punctuation::Comma => ",",
+        };
+        f.write_str(symbol)
+    }
+}
+
+/// A number literal as written in the source, to be converted into a value by
+/// [`crate::numeric::FromLiteral`] of the numeric backend.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Literal {
+    /// The digits without separators or radix prefix, with a decimal point if the literal has
+    /// a fractional part, e.g. `6.02` for `6.02e23` or `FF` for `0xFF`.
+    pub digits: String,
+    /// The radix of the digits, 16 for `0x`, 8 for `0o`, 2 for `0b` and 10 otherwise.
+    pub radix: u32,
+    /// The power of ten the digits are multiplied by, `23` for `6.02e23`.
+    pub exponent: i64,
+    /// Whether the literal ends with the imaginary unit suffix `i`, as in `2.5i`.
+    pub imaginary: bool,
+}
+
+impl Literal {
+    /// A decimal literal without exponent, e.g. `Literal::decimal("1.5")`.
+    pub fn decimal(digits: &str) -> Self {
+        Self {
+            digits: digits.to_owned(),
+            radix: 10,
+            exponent: 0,
+            imaginary: false,
+        }
+    }
+}
+
+impl Display for Literal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let prefix = match self.radix {
+            16 => "0x",
+            8 => "0o",
+            2 => "0b",
+            _ => "",
+        };
+        write!(f, "{prefix}{}", self.digits)?;
+        if self.exponent != 0 {
+            write!(f, "e{}", self.exponent)?;
+        }
+        if self.imaginary {
+            f.write_str("i")?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
-pub enum Token<N: NumericValue> {
-    Number(N),
+pub enum Token {
+    Number(Literal),
     Identifier(String),
+    Keyword(Keyword),
     Operator(Operator),
     Punctuation(Punctuation),
+    /// `$n`, the `n`-th result of the session.
+    ResultRef(usize),
     Eof,
 }
+
+impl Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Number(literal) => write!(f, "number `{literal}`"),
+            Token::Identifier(name) => write!(f, "identifier `{name}`"),
+            Token::Keyword(keyword) => write!(f, "`{keyword}`"),
+            Token::Operator(operator) => write!(f, "`{operator}`"),
+            Token::Punctuation(punctuation) => write!(f, "`{punctuation}`"),
+            Token::ResultRef(number) => write!(f, "`${number}`"),
+            Token::Eof => f.write_str("end of input"),
+        }
+    }
+}
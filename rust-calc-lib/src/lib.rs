@@ -1,6 +1,11 @@
 #![allow(dead_code)]
 
+pub mod derivative;
 pub mod evaluator;
+pub mod export;
+pub mod format;
 pub mod lexer;
 pub mod numeric;
+pub mod optimizer;
 pub mod parser;
+pub mod span;
@@ -0,0 +1,55 @@
+use std::collections::HashMap;
+
+use num_bigfloat::BigFloat;
+use num_traits::Float;
+
+use crate::numeric::{Arity, BuiltinFn};
+
+/// Builtin functions over arbitrary precision floats.
+pub struct BigFloatBuiltins;
+
+impl BuiltinFn<BigFloat> for BigFloatBuiltins {
+    fn names(&self) -> &'static [&'static str] {
+        &[
+            "sqrt", "sin", "cos", "tan", "exp", "ln", "deg2rad", "atan2", "log", "hypot", "max",
+            "min",
+        ]
+    }
+
+    fn arity(&self, name: &str) -> Option<Arity> {
+        match name {
+            "sqrt" | "sin" | "cos" | "tan" | "exp" | "ln" | "deg2rad" => Some(Arity::exact(1)),
+            "atan2" | "log" | "hypot" => Some(Arity::exact(2)),
+            "max" | "min" => Some(Arity::at_least(1)),
+            _ => None,
+        }
+    }
+
+    fn call(&self, name: &str, args: &[BigFloat]) -> Result<BigFloat, String> {
+        match (name, args) {
+            ("sqrt", [arg]) => Ok(arg.sqrt()),
+            ("sin", [arg]) => Ok(arg.sin()),
+            ("cos", [arg]) => Ok(arg.cos()),
+            ("tan", [arg]) => Ok(arg.tan()),
+            ("exp", [arg]) => Ok(arg.exp()),
+            ("ln", [arg]) => Ok(arg.ln()),
+            ("deg2rad", [arg]) => {
+                let pi = num_bigfloat::PI;
+                Ok(*arg * pi / BigFloat::from_f64(180.0))
+            }
+            ("atan2", [y, x]) => Ok(Float::atan2(*y, *x)),
+            ("log", [x, base]) => Ok(x.log(base)),
+            ("hypot", [x, y]) => Ok(Float::hypot(*x, *y)),
+            ("max", [first, rest @ ..]) => Ok(rest.iter().fold(*first, |acc, x| acc.max(*x))),
+            ("min", [first, rest @ ..]) => Ok(rest.iter().fold(*first, |acc, x| acc.min(*x))),
+            _ => Err(format!("unknown function `{name}`")),
+        }
+    }
+
+    fn constants(&self) -> HashMap<String, BigFloat> {
+        HashMap::from([
+            (String::from("pi"), num_bigfloat::PI),
+            (String::from("e"), num_bigfloat::E),
+        ])
+    }
+}
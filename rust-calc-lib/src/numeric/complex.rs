@@ -0,0 +1,125 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::f64::consts;
+
+use num_complex::Complex64;
+use num_traits::Zero;
+
+use crate::lexer::token::Literal;
+use crate::numeric::{Arity, BuiltinFn, FromLiteral, NumericValue, PortableValue};
+
+impl FromLiteral for Complex64 {
+    fn from_literal(literal: &Literal) -> Result<Self, String> {
+        f64::from_literal(literal).map(|re| Complex64::new(re, 0.0))
+    }
+}
+
+impl NumericValue for Complex64 {
+    fn checked_pow(self, exponent: Self) -> Result<Self, String> {
+        if self.is_zero() {
+            return if exponent.is_zero() {
+                Ok(Complex64::new(1.0, 0.0))
+            } else if exponent.re > 0.0 {
+                Ok(Complex64::zero())
+            } else {
+                Err("Division by zero".to_owned())
+            };
+        }
+        // integer powers by repeated multiplication keep results like `i^2 == -1` exact
+        if exponent.im == 0.0 && exponent.re.fract() == 0.0 && exponent.re.abs() <= i32::MAX as f64
+        {
+            return Ok(self.powi(exponent.re as i32));
+        }
+        Ok(self.powc(exponent))
+    }
+
+    /// Orders by the real part, the evaluator only compares values for which [`Self::is_real`]
+    /// holds.
+    fn compare(&self, other: &Self) -> Option<Ordering> {
+        self.re.partial_cmp(&other.re)
+    }
+
+    fn is_real(&self) -> bool {
+        self.im == 0.0
+    }
+
+    fn imaginary_unit() -> Option<Self> {
+        Some(Complex64::i())
+    }
+
+    fn to_complex(&self) -> Complex64 {
+        *self
+    }
+
+    fn to_portable(&self) -> PortableValue {
+        match self.im == 0.0 {
+            true => f64::to_portable(&self.re),
+            false => PortableValue::Complex(*self),
+        }
+    }
+
+    fn from_portable(value: &PortableValue) -> Option<Self> {
+        match value {
+            PortableValue::Real(_) => f64::from_portable(value).map(|re| Complex64::new(re, 0.0)),
+            PortableValue::Complex(value) => Some(*value),
+        }
+    }
+}
+
+/// Builtin functions defined on the whole complex plane, so that `sqrt(-1)` is `i`.
+pub struct ComplexBuiltins;
+
+impl BuiltinFn<Complex64> for ComplexBuiltins {
+    fn names(&self) -> &'static [&'static str] {
+        &[
+            "sqrt", "exp", "ln", "sin", "cos", "tan", "asin", "acos", "atan", "sinh", "cosh",
+            "tanh", "abs", "arg", "re", "im", "conj", "log", "polar",
+        ]
+    }
+
+    fn arity(&self, name: &str) -> Option<Arity> {
+        match name {
+            "sqrt" | "exp" | "ln" | "sin" | "cos" | "tan" | "asin" | "acos" | "atan" | "sinh"
+            | "cosh" | "tanh" | "abs" | "arg" | "re" | "im" | "conj" => Some(Arity::exact(1)),
+            "log" | "polar" => Some(Arity::exact(2)),
+            _ => None,
+        }
+    }
+
+    fn call(&self, name: &str, args: &[Complex64]) -> Result<Complex64, String> {
+        let real = |x: f64| Complex64::new(x, 0.0);
+        match (name, args) {
+            ("sqrt", [z]) => Ok(z.sqrt()),
+            ("exp", [z]) => Ok(z.exp()),
+            ("ln", [z]) => Ok(z.ln()),
+            ("sin", [z]) => Ok(z.sin()),
+            ("cos", [z]) => Ok(z.cos()),
+            ("tan", [z]) => Ok(z.tan()),
+            ("asin", [z]) => Ok(z.asin()),
+            ("acos", [z]) => Ok(z.acos()),
+            ("atan", [z]) => Ok(z.atan()),
+            ("sinh", [z]) => Ok(z.sinh()),
+            ("cosh", [z]) => Ok(z.cosh()),
+            ("tanh", [z]) => Ok(z.tanh()),
+            ("abs", [z]) => Ok(real(z.norm())),
+            ("arg", [z]) => Ok(real(z.arg())),
+            ("re", [z]) => Ok(real(z.re)),
+            ("im", [z]) => Ok(real(z.im)),
+            ("conj", [z]) => Ok(z.conj()),
+            ("log", [z, base]) => Ok(z.ln() / base.ln()),
+            ("polar", [r, theta]) if r.im == 0.0 && theta.im == 0.0 => {
+                Ok(Complex64::from_polar(r.re, theta.re))
+            }
+            ("polar", _) => Err("`polar` takes a real radius and angle".to_owned()),
+            _ => Err(format!("unknown function `{name}`")),
+        }
+    }
+
+    fn constants(&self) -> HashMap<String, Complex64> {
+        HashMap::from([
+            (String::from("pi"), Complex64::new(consts::PI, 0.0)),
+            (String::from("e"), Complex64::new(consts::E, 0.0)),
+            (String::from("i"), Complex64::i()),
+        ])
+    }
+}
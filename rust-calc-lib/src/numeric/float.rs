@@ -0,0 +1,87 @@
+use std::collections::HashMap;
+use std::f64::consts::{self, PI};
+
+use crate::numeric::{Arity, BuiltinFn};
+
+/// Builtin functions over machine precision floats.
+pub struct FloatBuiltins;
+
+impl BuiltinFn<f64> for FloatBuiltins {
+    fn names(&self) -> &'static [&'static str] {
+        &[
+            "sqrt", "sin", "cos", "tan", "asin", "acos", "atan", "exp", "ln", "abs", "floor",
+            "ceil", "round", "trunc", "deg2rad", "atan2", "log", "hypot", "max", "min",
+        ]
+    }
+
+    fn arity(&self, name: &str) -> Option<Arity> {
+        match name {
+            "sqrt" | "sin" | "cos" | "tan" | "asin" | "acos" | "atan" | "exp" | "ln" | "abs"
+            | "floor" | "ceil" | "round" | "trunc" | "deg2rad" => Some(Arity::exact(1)),
+            "atan2" | "log" | "hypot" => Some(Arity::exact(2)),
+            "max" | "min" => Some(Arity::at_least(1)),
+            _ => None,
+        }
+    }
+
+    fn call(&self, name: &str, args: &[f64]) -> Result<f64, String> {
+        match (name, args) {
+            ("sqrt", [x]) => Ok(x.sqrt()),
+            ("sin", [x]) => Ok(x.sin()),
+            ("cos", [x]) => Ok(x.cos()),
+            ("tan", [x]) => Ok(x.tan()),
+            ("asin", [x]) => Ok(x.asin()),
+            ("acos", [x]) => Ok(x.acos()),
+            ("atan", [x]) => Ok(x.atan()),
+            ("exp", [x]) => Ok(x.exp()),
+            ("ln", [x]) => Ok(x.ln()),
+            ("abs", [x]) => Ok(x.abs()),
+            ("floor", [x]) => Ok(x.floor()),
+            ("ceil", [x]) => Ok(x.ceil()),
+            ("round", [x]) => Ok(x.round()),
+            ("trunc", [x]) => Ok(x.trunc()),
+            ("deg2rad", [x]) => Ok(x.to_radians()),
+            ("atan2", [y, x]) => Ok(y.atan2(*x)),
+            ("log", [x, base]) => Ok(x.log(*base)),
+            ("hypot", [x, y]) => Ok(x.hypot(*y)),
+            ("max", [first, rest @ ..]) => Ok(rest.iter().fold(*first, |acc, x| acc.max(*x))),
+            ("min", [first, rest @ ..]) => Ok(rest.iter().fold(*first, |acc, x| acc.min(*x))),
+            _ => Err(format!("unknown function `{name}`")),
+        }
+    }
+
+    fn constants(&self) -> HashMap<String, f64> {
+        HashMap::from([
+            (String::from("pi"), consts::PI),
+            (String::from("e"), consts::E),
+        ])
+    }
+}
+
+/// The gamma function, by the Lanczos approximation with `g = 7` for arguments of at least one
+/// half and by the reflection formula below that. Accurate to about 15 digits.
+pub(crate) fn gamma(x: f64) -> f64 {
+    const G: f64 = 7.0;
+    const COEFFICIENTS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+    if x < 0.5 {
+        // Γ(x) Γ(1 - x) = π / sin(πx)
+        return PI / ((PI * x).sin() * gamma(1.0 - x));
+    }
+    let x = x - 1.0;
+    let t = x + G + 0.5;
+    let series = COEFFICIENTS[1..]
+        .iter()
+        .zip(1..)
+        .fold(COEFFICIENTS[0], |sum, (c, i)| sum + c / (x + f64::from(i)));
+    (2.0 * PI).sqrt() * t.powf(x + 0.5) * (-t).exp() * series
+}
@@ -0,0 +1,176 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt::{self, Display};
+use std::ops::{Add, Div, Mul, Rem, Sub};
+
+use num_bigint::{BigInt, ParseBigIntError};
+use num_complex::Complex64;
+use num_integer::Integer as _;
+use num_rational::BigRational;
+use num_traits::{Num, One, Signed, ToPrimitive, Zero};
+
+use crate::lexer::token::Literal;
+use crate::numeric::rational::{MAX_EXPONENT, Rational};
+use crate::numeric::{Arity, BuiltinFn, FromLiteral, NumericValue, PortableValue};
+
+/// An arbitrarily large integer. Division truncates toward zero, so `7 / 2` is `3`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Integer(BigInt);
+
+impl Integer {
+    pub fn value(&self) -> &BigInt {
+        &self.0
+    }
+}
+
+impl From<BigInt> for Integer {
+    fn from(value: BigInt) -> Self {
+        Self(value)
+    }
+}
+
+impl From<i64> for Integer {
+    fn from(value: i64) -> Self {
+        Self(BigInt::from(value))
+    }
+}
+
+impl Display for Integer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+macro_rules! forward_binary_op {
+    ($($op:ident::$method:ident),*) => {
+        $(
+            impl $op for Integer {
+                type Output = Integer;
+
+                fn $method(self, rhs: Integer) -> Integer {
+                    Integer(self.0.$method(rhs.0))
+                }
+            }
+        )*
+    };
+}
+
+forward_binary_op!(Add::add, Sub::sub, Mul::mul, Div::div, Rem::rem);
+
+impl Zero for Integer {
+    fn zero() -> Self {
+        Self(BigInt::zero())
+    }
+
+    fn is_zero(&self) -> bool {
+        self.0.is_zero()
+    }
+}
+
+impl One for Integer {
+    fn one() -> Self {
+        Self(BigInt::one())
+    }
+}
+
+impl Num for Integer {
+    type FromStrRadixErr = ParseBigIntError;
+
+    fn from_str_radix(str: &str, radix: u32) -> Result<Self, ParseBigIntError> {
+        BigInt::from_str_radix(str, radix).map(Self)
+    }
+}
+
+impl FromLiteral for Integer {
+    fn from_literal(literal: &Literal) -> Result<Self, String> {
+        let value = Rational::from_literal(literal)?;
+        if !value.is_integer() {
+            return Err(format!("`{literal}` is not an integer"));
+        }
+        Ok(Integer(value.numerator().clone()))
+    }
+}
+
+impl NumericValue for Integer {
+    /// Only non-negative exponents are supported, except for the bases one and minus one whose
+    /// negative powers are still integers.
+    fn checked_pow(self, exponent: Self) -> Result<Self, String> {
+        let Some(power) = exponent
+            .0
+            .abs()
+            .to_u32()
+            .filter(|power| *power <= MAX_EXPONENT)
+        else {
+            return Err(format!("exponent {exponent} is too large"));
+        };
+        if exponent.0.is_negative() {
+            if self.is_zero() {
+                return Err("Division by zero".to_owned());
+            }
+            if self.0.abs() != BigInt::one() {
+                return Err(format!("{self} ^ {exponent} is not an integer"));
+            }
+        }
+        Ok(Integer(self.0.pow(power)))
+    }
+
+    fn compare(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+
+    fn to_complex(&self) -> Complex64 {
+        Complex64::new(self.0.to_f64().unwrap_or(f64::NAN), 0.0)
+    }
+
+    fn to_portable(&self) -> PortableValue {
+        PortableValue::Real(BigRational::from_integer(self.0.clone()))
+    }
+
+    fn from_portable(value: &PortableValue) -> Option<Self> {
+        match value {
+            PortableValue::Real(value) if value.is_integer() => Some(Self(value.to_integer())),
+            _ => None,
+        }
+    }
+}
+
+/// Builtin functions over [`Integer`].
+pub struct IntegerBuiltins;
+
+impl BuiltinFn<Integer> for IntegerBuiltins {
+    fn names(&self) -> &'static [&'static str] {
+        &["abs", "sign", "isqrt", "gcd", "lcm", "max", "min"]
+    }
+
+    fn arity(&self, name: &str) -> Option<Arity> {
+        match name {
+            "abs" | "sign" | "isqrt" => Some(Arity::exact(1)),
+            "gcd" | "lcm" | "max" | "min" => Some(Arity::at_least(1)),
+            _ => None,
+        }
+    }
+
+    fn call(&self, name: &str, args: &[Integer]) -> Result<Integer, String> {
+        match (name, args) {
+            ("abs", [x]) => Ok(Integer(x.0.abs())),
+            ("sign", [x]) => Ok(Integer(x.0.signum())),
+            ("isqrt", [x]) if x.0.is_negative() => Err(format!(
+                "square root of negative number {x} is not a real number"
+            )),
+            ("isqrt", [x]) => Ok(Integer(x.0.sqrt())),
+            ("gcd", [first, rest @ ..]) => Ok(Integer(
+                rest.iter().fold(first.0.clone(), |acc, x| acc.gcd(&x.0)),
+            )),
+            ("lcm", [first, rest @ ..]) => Ok(Integer(
+                rest.iter().fold(first.0.clone(), |acc, x| acc.lcm(&x.0)),
+            )),
+            ("max", [first, rest @ ..]) => Ok(rest.iter().fold(first, Ord::max).clone()),
+            ("min", [first, rest @ ..]) => Ok(rest.iter().fold(first, Ord::min).clone()),
+            _ => Err(format!("unknown function `{name}`")),
+        }
+    }
+
+    fn constants(&self) -> HashMap<String, Integer> {
+        HashMap::new()
+    }
+}
@@ -0,0 +1,262 @@
+pub mod bigfloat;
+pub mod complex;
+pub mod float;
+pub mod integer;
+pub mod rational;
+#[cfg(test)]
+mod tests;
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt::{self, Display};
+
+use num_bigfloat::BigFloat;
+use num_bigint::BigInt;
+use num_complex::Complex64;
+use num_rational::BigRational;
+use num_traits::{Float, FromPrimitive, Num, One, Pow, ToPrimitive, Zero};
+
+use crate::lexer::token::Literal;
+
+pub trait NumericValue: Num + Clone + FromLiteral {
+    /// Raises `self` to the power of `exponent`, failing if the backend cannot represent the
+    /// result.
+    fn checked_pow(self, exponent: Self) -> Result<Self, String>;
+
+    /// Returns the factorial `self!`, failing unless `self` is a non-negative integer no larger
+    /// than [`MAX_FACTORIAL`].
+    fn factorial(self) -> Result<Self, String> {
+        integer_factorial(self)
+    }
+
+    /// Orders two real values, or returns `None` if they are unordered, like NaN.
+    fn compare(&self, other: &Self) -> Option<Ordering>;
+
+    /// Whether the value lies on the real line and so can be ordered.
+    fn is_real(&self) -> bool {
+        true
+    }
+
+    /// Returns the imaginary unit, or `None` if the backend only has real numbers.
+    fn imaginary_unit() -> Option<Self> {
+        None
+    }
+
+    /// Converts the value to a complex number. Precision beyond an `f64` is lost.
+    fn to_complex(&self) -> Complex64;
+
+    /// Converts the value for moving it to another backend, see [`PortableValue`].
+    fn to_portable(&self) -> PortableValue;
+
+    /// Converts a value from another backend as closely as this backend can represent it, or
+    /// returns `None` if it has no representation here, like `1.5` for integers or `i` for real
+    /// numbers. The conversion was exact if [`Self::to_portable`] of the result gives `value`
+    /// back.
+    fn from_portable(value: &PortableValue) -> Option<Self>;
+}
+
+/// A value on its way from one backend to another.
+#[derive(Debug, Clone)]
+pub enum PortableValue {
+    /// A finite real number, exactly. Floats count as the shortest decimal that reads back as
+    /// them, so that `0.1` is one tenth rather than the binary fraction closest to it.
+    Real(BigRational),
+    /// A value without a rational form, one that is complex, infinite or NaN, to the precision
+    /// of an `f64`.
+    Complex(Complex64),
+}
+
+/// NaN equals itself here, as it stays NaN when moving between backends.
+impl PartialEq for PortableValue {
+    fn eq(&self, other: &Self) -> bool {
+        let same = |a: f64, b: f64| a == b || (a.is_nan() && b.is_nan());
+        match (self, other) {
+            (PortableValue::Real(a), PortableValue::Real(b)) => a == b,
+            (PortableValue::Complex(a), PortableValue::Complex(b)) => {
+                same(a.re, b.re) && same(a.im, b.im)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Reads a decimal such as `-1.25e-3` exactly, as floats are written by `Display`. Returns `None`
+/// for anything else, like `inf` or `NaN`.
+pub(crate) fn decimal_rational(text: &str) -> Option<BigRational> {
+    let (mantissa, exponent) = match text.split_once('e') {
+        Some((mantissa, exponent)) => (mantissa, exponent.parse::<i64>().ok()?),
+        None => (text, 0),
+    };
+    let (integer, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let digits: BigInt = format!("{integer}{fraction}").parse().ok()?;
+    let exponent = exponent - fraction.len() as i64;
+    let scale = BigInt::from(10).pow(u32::try_from(exponent.unsigned_abs()).ok()?);
+    Some(match exponent < 0 {
+        true => BigRational::new(digits, scale),
+        false => BigRational::from_integer(digits * scale),
+    })
+}
+
+/// Conversion from the number literals of the source, which each backend reads as exactly as it
+/// can represent them.
+pub trait FromLiteral: Sized {
+    /// Converts the real value of `literal`, or returns an error message if the backend cannot
+    /// represent it, like `1.5` for integers. An imaginary suffix is applied by the parser with
+    /// [`NumericValue::imaginary_unit`] and is ignored here.
+    fn from_literal(literal: &Literal) -> Result<Self, String>;
+}
+
+/// Writes the value of `literal` in decimal, with the exponent in scientific notation, for
+/// backends that parse decimal strings.
+fn decimal_text(literal: &Literal) -> Result<String, String> {
+    if literal.radix == 10 {
+        return Ok(format!("{}e{}", literal.digits, literal.exponent));
+    }
+    BigInt::from_str_radix(&literal.digits, literal.radix)
+        .map(|value| value.to_string())
+        .map_err(|_| invalid_literal(literal))
+}
+
+fn invalid_literal(literal: &Literal) -> String {
+    format!("invalid number `{literal}`")
+}
+
+macro_rules! impl_float_numeric_value {
+    ($($float:ty),*) => {
+        $(
+            impl FromLiteral for $float {
+                fn from_literal(literal: &Literal) -> Result<Self, String> {
+                    <$float as Num>::from_str_radix(&decimal_text(literal)?, 10)
+                        .map_err(|_| invalid_literal(literal))
+                }
+            }
+
+            impl NumericValue for $float {
+                fn checked_pow(self, exponent: Self) -> Result<Self, String> {
+                    Ok(Pow::pow(self, exponent))
+                }
+
+                /// Extends the factorial to non-integers as `x! = Γ(x + 1)`, computed in
+                /// machine precision.
+                fn factorial(self) -> Result<Self, String> {
+                    if self.clone() % Self::one() == Self::zero() {
+                        return integer_factorial(self);
+                    }
+                    let x = ToPrimitive::to_f64(&self).unwrap_or(f64::NAN);
+                    FromPrimitive::from_f64(float::gamma(x + 1.0))
+                        .ok_or_else(|| format!("cannot compute the factorial of {x}"))
+                }
+
+                fn compare(&self, other: &Self) -> Option<Ordering> {
+                    self.partial_cmp(other)
+                }
+
+                fn to_complex(&self) -> Complex64 {
+                    Complex64::new(ToPrimitive::to_f64(self).unwrap_or(f64::NAN), 0.0)
+                }
+
+                fn to_portable(&self) -> PortableValue {
+                    match decimal_rational(&self.to_string()) {
+                        Some(value) => PortableValue::Real(value),
+                        None => PortableValue::Complex(self.to_complex()),
+                    }
+                }
+
+                /// Divides the numerator by the denominator of a rational, rejecting values out
+                /// of range.
+                fn from_portable(value: &PortableValue) -> Option<Self> {
+                    match value {
+                        PortableValue::Real(value) => {
+                            let part = |part: &BigInt| {
+                                <$float as Num>::from_str_radix(&part.to_string(), 10).ok()
+                            };
+                            Some(part(value.numer())? / part(value.denom())?)
+                                .filter(|value| Float::is_finite(*value))
+                        }
+                        PortableValue::Complex(value) if value.im == 0.0 => {
+                            FromPrimitive::from_f64(value.re)
+                        }
+                        PortableValue::Complex(_) => None,
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_float_numeric_value!(f32, f64, BigFloat);
+
+/// The largest number whose factorial is computed, as exact backends take long to multiply
+/// past it.
+pub const MAX_FACTORIAL: u32 = 10_000;
+
+/// Multiplies out the factorial of a non-negative integer.
+fn integer_factorial<N: NumericValue>(n: N) -> Result<N, String> {
+    let is_integer = n.is_real() && n.clone() % N::one() == N::zero();
+    let value = n.to_complex().re;
+    if !is_integer || value < 0.0 {
+        return Err("factorial is only defined for non-negative integers".to_owned());
+    }
+    if value > f64::from(MAX_FACTORIAL) {
+        return Err(format!("factorial of a number above {MAX_FACTORIAL}"));
+    }
+    let (mut factor, mut result) = (N::one(), N::one());
+    for _ in 1..value as u32 {
+        factor = factor + N::one();
+        result = result * factor.clone();
+    }
+    Ok(result)
+}
+
+/// Converts a truth value into a number, one for true and zero for false.
+pub(crate) fn from_bool<N: NumericValue>(value: bool) -> N {
+    if value { N::one() } else { N::zero() }
+}
+
+/// The number of arguments a builtin function accepts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Arity {
+    pub min: usize,
+    pub max: Option<usize>,
+}
+
+impl Arity {
+    pub fn exact(count: usize) -> Self {
+        Self {
+            min: count,
+            max: Some(count),
+        }
+    }
+
+    pub fn at_least(count: usize) -> Self {
+        Self {
+            min: count,
+            max: None,
+        }
+    }
+
+    pub fn accepts(&self, count: usize) -> bool {
+        count >= self.min && self.max.is_none_or(|max| count <= max)
+    }
+}
+
+impl Display for Arity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.max {
+            Some(max) if max == self.min => write!(f, "{max}"),
+            Some(max) => write!(f, "{} to {max}", self.min),
+            None => write!(f, "at least {}", self.min),
+        }
+    }
+}
+
+pub trait BuiltinFn<N: NumericValue> {
+    /// The names of all builtin functions.
+    fn names(&self) -> &'static [&'static str];
+    /// Returns the arity of the builtin function `name`, or `None` if there is no such function.
+    fn arity(&self, name: &str) -> Option<Arity>;
+    /// Calls the builtin function `name`, returning an error message if the call fails. The
+    /// evaluator checks `args` against [`BuiltinFn::arity`] beforehand.
+    fn call(&self, name: &str, args: &[N]) -> Result<N, String>;
+    fn constants(&self) -> HashMap<String, N>;
+}
@@ -0,0 +1,268 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{self, Display};
+use std::ops::{Add, Div, Mul, Rem, Sub};
+
+use num_bigint::BigInt;
+use num_complex::Complex64;
+use num_rational::BigRational;
+use num_traits::{Num, One, Signed, ToPrimitive, Zero};
+
+use crate::lexer::token::Literal;
+use crate::numeric::{Arity, BuiltinFn, FromLiteral, NumericValue, PortableValue};
+
+/// The largest exponent magnitude [`Rational::checked_pow`] accepts, so that a typo like
+/// `3^1000000000` fails instead of computing for hours.
+pub const MAX_EXPONENT: u32 = 100_000;
+
+/// An exact fraction of two arbitrarily large integers, always kept in lowest terms.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Rational(BigRational);
+
+impl Rational {
+    /// Creates the fraction `numerator / denominator`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `denominator` is zero.
+    pub fn new(numerator: BigInt, denominator: BigInt) -> Self {
+        Self(BigRational::new(numerator, denominator))
+    }
+
+    pub fn numerator(&self) -> &BigInt {
+        self.0.numer()
+    }
+
+    pub fn denominator(&self) -> &BigInt {
+        self.0.denom()
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.0.is_integer()
+    }
+
+    /// Returns the exact `n`-th root, or an error if it is irrational or not a real number.
+    fn exact_root(&self, n: u32) -> Result<Rational, String> {
+        if self.0.is_negative() && n.is_multiple_of(2) {
+            return Err(format!(
+                "root of negative number {self} is not a real number"
+            ));
+        }
+        let exact_root = |value: &BigInt| {
+            let root = value.nth_root(n);
+            (root.pow(n) == *value).then_some(root)
+        };
+        match (exact_root(self.numerator()), exact_root(self.denominator())) {
+            (Some(numerator), Some(denominator)) => Ok(Rational::new(numerator, denominator)),
+            _ => Err(format!(
+                "root of {self} is irrational and cannot be represented exactly"
+            )),
+        }
+    }
+}
+
+impl From<BigInt> for Rational {
+    fn from(value: BigInt) -> Self {
+        Self(BigRational::from_integer(value))
+    }
+}
+
+impl From<i64> for Rational {
+    fn from(value: i64) -> Self {
+        Self::from(BigInt::from(value))
+    }
+}
+
+impl Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_integer() {
+            write!(f, "{}", self.numerator())
+        } else {
+            write!(f, "{}/{}", self.numerator(), self.denominator())
+        }
+    }
+}
+
+macro_rules! forward_binary_op {
+    ($($op:ident::$method:ident),*) => {
+        $(
+            impl $op for Rational {
+                type Output = Rational;
+
+                fn $method(self, rhs: Rational) -> Rational {
+                    Rational(self.0.$method(rhs.0))
+                }
+            }
+        )*
+    };
+}
+
+forward_binary_op!(Add::add, Sub::sub, Mul::mul, Div::div, Rem::rem);
+
+impl Zero for Rational {
+    fn zero() -> Self {
+        Self(BigRational::zero())
+    }
+
+    fn is_zero(&self) -> bool {
+        self.0.is_zero()
+    }
+}
+
+impl One for Rational {
+    fn one() -> Self {
+        Self(BigRational::one())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseRationalError;
+
+impl Display for ParseRationalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid rational literal")
+    }
+}
+
+impl Error for ParseRationalError {}
+
+impl Num for Rational {
+    type FromStrRadixErr = ParseRationalError;
+
+    /// Parses an integer (`12`), an exact decimal (`0.25`) or a fraction (`7/12`).
+    fn from_str_radix(str: &str, radix: u32) -> Result<Self, ParseRationalError> {
+        let parse_int =
+            |digits: &str| BigInt::from_str_radix(digits, radix).map_err(|_| ParseRationalError);
+        if let Some((numerator, denominator)) = str.split_once('/') {
+            let denominator = parse_int(denominator)?;
+            if denominator.is_zero() {
+                return Err(ParseRationalError);
+            }
+            return Ok(Rational::new(parse_int(numerator)?, denominator));
+        }
+        let (integer, fraction) = str.split_once('.').unwrap_or((str, ""));
+        if fraction.starts_with(['+', '-']) {
+            return Err(ParseRationalError);
+        }
+        let numerator = parse_int(&format!("{integer}{fraction}"))?;
+        let denominator = BigInt::from(radix).pow(fraction.len() as u32);
+        Ok(Rational::new(numerator, denominator))
+    }
+}
+
+/// Reads literals exactly, so `0.1` is one tenth and `1.5e-3` is `3/2000`.
+impl FromLiteral for Rational {
+    fn from_literal(literal: &Literal) -> Result<Self, String> {
+        let (integer, fraction) = literal
+            .digits
+            .split_once('.')
+            .unwrap_or((&literal.digits, ""));
+        let mantissa = BigInt::from_str_radix(&format!("{integer}{fraction}"), literal.radix)
+            .map_err(|_| format!("invalid number `{literal}`"))?;
+        let exponent = literal.exponent - fraction.len() as i64;
+        let Some(power) = u32::try_from(exponent.unsigned_abs())
+            .ok()
+            .filter(|power| *power <= MAX_EXPONENT)
+        else {
+            return Err(format!("exponent of `{literal}` is too large"));
+        };
+        let scale = BigInt::from(10).pow(power);
+        Ok(match exponent < 0 {
+            true => Rational::new(mantissa, scale),
+            false => Rational::from(mantissa * scale),
+        })
+    }
+}
+
+impl NumericValue for Rational {
+    /// Integer powers are always exact. A fractional exponent `p/q` only succeeds if the `q`-th
+    /// root of `self` is rational, so `4^(1/2)` is `2` while `2^(1/2)` is an error.
+    fn checked_pow(self, exponent: Self) -> Result<Self, String> {
+        let power = exponent
+            .numerator()
+            .to_i32()
+            .filter(|power| power.unsigned_abs() <= MAX_EXPONENT);
+        let root = exponent
+            .denominator()
+            .to_u32()
+            .filter(|root| *root <= MAX_EXPONENT);
+        let (Some(power), Some(root)) = (power, root) else {
+            return Err(format!("exponent {exponent} is too large"));
+        };
+        let base = if root == 1 {
+            self
+        } else {
+            self.exact_root(root)?
+        };
+        if power < 0 && base.is_zero() {
+            return Err("Division by zero".to_owned());
+        }
+        Ok(Rational(base.0.pow(power)))
+    }
+
+    fn compare(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+
+    fn to_complex(&self) -> Complex64 {
+        Complex64::new(self.0.to_f64().unwrap_or(f64::NAN), 0.0)
+    }
+
+    fn to_portable(&self) -> PortableValue {
+        PortableValue::Real(self.0.clone())
+    }
+
+    fn from_portable(value: &PortableValue) -> Option<Self> {
+        match value {
+            PortableValue::Real(value) => Some(Rational(value.clone())),
+            PortableValue::Complex(_) => None,
+        }
+    }
+}
+
+/// Builtin functions that keep results exact over [`Rational`].
+pub struct RationalBuiltins;
+
+impl BuiltinFn<Rational> for RationalBuiltins {
+    fn names(&self) -> &'static [&'static str] {
+        &[
+            "abs", "floor", "ceil", "round", "trunc", "num", "den", "sqrt", "root", "max", "min",
+        ]
+    }
+
+    fn arity(&self, name: &str) -> Option<Arity> {
+        match name {
+            "abs" | "floor" | "ceil" | "round" | "trunc" | "num" | "den" | "sqrt" => {
+                Some(Arity::exact(1))
+            }
+            "root" => Some(Arity::exact(2)),
+            "max" | "min" => Some(Arity::at_least(1)),
+            _ => None,
+        }
+    }
+
+    fn call(&self, name: &str, args: &[Rational]) -> Result<Rational, String> {
+        match (name, args) {
+            ("abs", [x]) => Ok(Rational(x.0.abs())),
+            ("floor", [x]) => Ok(Rational(x.0.floor())),
+            ("ceil", [x]) => Ok(Rational(x.0.ceil())),
+            ("round", [x]) => Ok(Rational(x.0.round())),
+            ("trunc", [x]) => Ok(Rational(x.0.trunc())),
+            ("num", [x]) => Ok(Rational::from(x.numerator().clone())),
+            ("den", [x]) => Ok(Rational::from(x.denominator().clone())),
+            ("sqrt", [x]) => x.exact_root(2),
+            ("root", [x, n]) => match n.is_integer().then(|| n.numerator().to_u32()) {
+                Some(Some(n)) if n > 0 => x.exact_root(n),
+                _ => Err(format!("root degree {n} must be a positive integer")),
+            },
+            ("max", [first, rest @ ..]) => Ok(rest.iter().fold(first, Ord::max).clone()),
+            ("min", [first, rest @ ..]) => Ok(rest.iter().fold(first, Ord::min).clone()),
+            _ => Err(format!("unknown function `{name}`")),
+        }
+    }
+
+    fn constants(&self) -> HashMap<String, Rational> {
+        HashMap::new()
+    }
+}
@@ -0,0 +1,386 @@
+use num_bigint::BigInt;
+use num_traits::Num;
+
+use crate::evaluator::{Evaluator, error::EvaluatorError};
+use crate::numeric::rational::{Rational, RationalBuiltins};
+
+fn rational(numerator: i64, denominator: i64) -> Rational {
+    Rational::new(BigInt::from(numerator), BigInt::from(denominator))
+}
+
+fn evaluate(input: &str) -> Result<Option<Rational>, EvaluatorError> {
+    Evaluator::new(RationalBuiltins).parse(input)
+}
+
+/// Macro to generate exact arithmetic tests
+macro_rules! rational_test {
+    ($name:ident, $input:expr, $numerator:expr, $denominator:expr) => {
+        #[test]
+        fn $name() {
+            assert_eq!(
+                evaluate($input).unwrap(),
+                Some(rational($numerator, $denominator)),
+                "Result mismatch in test '{}'\ninput: {}",
+                stringify!($name),
+                $input,
+            );
+        }
+    };
+}
+
+rational_test!(thirds_are_exact, "1 / 3 * 3;", 1, 1);
+rational_test!(fractions_stay_fractions, "1 / 3 + 1 / 4;", 7, 12);
+rational_test!(decimals_are_exact, "0.1 + 0.2;", 3, 10);
+rational_test!(integer_power, "(2 / 3) ^ 3;", 8, 27);
+rational_test!(negative_power, "2 ^ -2;", 1, 4);
+rational_test!(rational_root, "(8 / 27) ^ (-1 / 3);", 3, 2);
+rational_test!(odd_root_of_negative, "(-8) ^ (1 / 3);", -2, 1);
+rational_test!(exact_sqrt, "sqrt(9 / 16);", 3, 4);
+rational_test!(
+    rounding,
+    "floor(7 / 2) + ceil(7 / 2) + round(-5 / 2);",
+    4,
+    1
+);
+rational_test!(parts, "num(6 / 8) * 10 + den(6 / 8);", 34, 1);
+rational_test!(
+    extremes,
+    "max(1 / 2, 2 / 3, 3 / 5) - min(1 / 2, 2 / 3);",
+    1,
+    6
+);
+rational_test!(comparison, "if 1 / 3 < 0.3334 then 1 else 0;", 1, 1);
+rational_test!(exact_percent, "12.5%;", 1, 8);
+rational_test!(fractional_remainder, "(7 / 2) mod (4 / 3);", 5, 6);
+rational_test!(fractional_floor_division, "(-7 / 2) // (4 / 3);", -3, 1);
+
+#[test]
+fn irrational_results_fail() {
+    for input in [
+        "2 ^ (1 / 2);",
+        "sqrt(2);",
+        "(-4) ^ (1 / 2);",
+        "root(10, 3);",
+    ] {
+        assert!(
+            matches!(evaluate(input), Err(EvaluatorError::OperationFailed(..))),
+            "input: {input}"
+        );
+    }
+}
+
+#[test]
+fn huge_exponents_fail() {
+    assert!(matches!(
+        evaluate("3 ^ 1000000000;"),
+        Err(EvaluatorError::OperationFailed(..))
+    ));
+}
+
+#[test]
+fn division_by_zero_fails() {
+    for input in ["1 / 0;", "0 ^ -1;"] {
+        assert!(
+            matches!(evaluate(input), Err(EvaluatorError::OperationFailed(..))),
+            "input: {input}"
+        );
+    }
+}
+
+#[test]
+fn parse_rational_literals() {
+    assert_eq!(Rational::from_str_radix("42", 10), Ok(rational(42, 1)));
+    assert_eq!(Rational::from_str_radix("1.25", 10), Ok(rational(5, 4)));
+    assert_eq!(Rational::from_str_radix("7/12", 10), Ok(rational(7, 12)));
+    assert_eq!(Rational::from_str_radix("-0.5", 10), Ok(rational(-1, 2)));
+    assert!(Rational::from_str_radix("1/0", 10).is_err());
+    assert!(Rational::from_str_radix("1.-5", 10).is_err());
+    assert!(Rational::from_str_radix(".", 10).is_err());
+}
+
+#[test]
+fn display() {
+    assert_eq!(rational(7, 12).to_string(), "7/12");
+    assert_eq!(rational(-6, 3).to_string(), "-2");
+}
+
+mod complex {
+    use num_complex::Complex64;
+
+    use crate::evaluator::{Evaluator, error::EvaluatorError};
+    use crate::numeric::complex::ComplexBuiltins;
+
+    fn evaluate(input: &str) -> Complex64 {
+        Evaluator::new(ComplexBuiltins)
+            .parse(input)
+            .unwrap()
+            .unwrap()
+    }
+
+    fn assert_close(input: &str, expected: Complex64) {
+        let actual = evaluate(input);
+        assert!(
+            (actual - expected).norm() < 1e-12,
+            "input: {input}\nactual: {actual}\nexpected: {expected}"
+        );
+    }
+
+    #[test]
+    fn square_root_of_negative() {
+        assert_eq!(evaluate("sqrt(-1);"), Complex64::i());
+    }
+
+    #[test]
+    fn powers_of_i_are_exact() {
+        assert_eq!(evaluate("i ^ 2;"), Complex64::new(-1.0, 0.0));
+        assert_eq!(evaluate("(2i) ^ -1;"), Complex64::new(0.0, -0.5));
+    }
+
+    #[test]
+    fn literals_and_functions() {
+        assert_eq!(evaluate("abs(3 + 4i);"), Complex64::new(5.0, 0.0));
+        assert_eq!(
+            evaluate("conj(1 + 2i) * (1 + 2i);"),
+            Complex64::new(5.0, 0.0)
+        );
+        assert_close("e ^ (i * pi);", Complex64::new(-1.0, 0.0));
+        assert_close("polar(2, pi / 2);", Complex64::new(0.0, 2.0));
+    }
+
+    #[test]
+    fn real_values_compare() {
+        assert_eq!(evaluate("(1 + 0i) < 2;"), Complex64::new(1.0, 0.0));
+    }
+
+    #[test]
+    fn complex_values_cannot_be_ordered() {
+        assert!(matches!(
+            Evaluator::new(ComplexBuiltins).parse("1 < i;"),
+            Err(EvaluatorError::OperationFailed(..))
+        ));
+    }
+}
+
+mod integer {
+    use crate::evaluator::{Evaluator, error::EvaluatorError};
+    use crate::numeric::integer::{Integer, IntegerBuiltins};
+
+    fn evaluate(input: &str) -> Result<Option<Integer>, EvaluatorError> {
+        Evaluator::new(IntegerBuiltins).parse(input)
+    }
+
+    #[test]
+    fn division_truncates() {
+        assert_eq!(evaluate("7 / 2;").unwrap(), Some(Integer::from(3)));
+        assert_eq!(evaluate("-7 / 2;").unwrap(), Some(Integer::from(-3)));
+    }
+
+    #[test]
+    fn large_powers_are_exact() {
+        assert_eq!(
+            evaluate("2 ^ 100;").unwrap().unwrap().to_string(),
+            "1267650600228229401496703205376"
+        );
+        assert_eq!(evaluate("(-1) ^ -3;").unwrap(), Some(Integer::from(-1)));
+    }
+
+    #[test]
+    fn factorials_are_exact() {
+        assert_eq!(
+            evaluate("25!;").unwrap().unwrap().to_string(),
+            "15511210043330985984000000"
+        );
+        assert_eq!(
+            evaluate("-7 // 2 + -7 mod 2;").unwrap(),
+            Some(Integer::from(-3))
+        );
+    }
+
+    #[test]
+    fn builtins() {
+        assert_eq!(
+            evaluate("gcd(12, 18) + lcm(4, 6) + isqrt(17);").unwrap(),
+            Some(Integer::from(22))
+        );
+    }
+
+    #[test]
+    fn fractions_fail() {
+        for input in ["2 ^ -1;", "1.5;", "isqrt(-4);"] {
+            assert!(evaluate(input).is_err(), "input: {input}");
+        }
+    }
+}
+
+mod conversion {
+    use num_bigfloat::BigFloat;
+    use num_bigint::BigInt;
+    use num_complex::Complex64;
+    use num_rational::BigRational;
+    use num_traits::Pow;
+
+    use crate::numeric::integer::Integer;
+    use crate::numeric::{NumericValue, PortableValue};
+
+    use super::rational;
+
+    fn real(numerator: i64, denominator: i64) -> PortableValue {
+        PortableValue::Real(BigRational::new(numerator.into(), denominator.into()))
+    }
+
+    #[test]
+    fn decimals_become_short_fractions() {
+        assert_eq!(0.1.to_portable(), real(1, 10));
+        assert_eq!(BigFloat::from_f64(-2.5e-3).to_portable(), real(-1, 400));
+        assert_eq!(
+            Complex64::new(1e15, 0.0).to_portable(),
+            real(10_i64.pow(15), 1)
+        );
+        assert_eq!(rational(-3, 4).to_portable(), real(-3, 4));
+        assert_eq!(
+            f64::NAN.to_portable(),
+            PortableValue::Complex(Complex64::new(f64::NAN, 0.0))
+        );
+    }
+
+    #[test]
+    fn exact_values_stay_exact() {
+        let big = BigInt::from(3).pow(50_u32);
+        let value = Integer::from(big.clone()).to_portable();
+        assert_eq!(
+            NumericValue::from_portable(&value),
+            Some(super::Rational::new(big, BigInt::from(1)))
+        );
+        assert_eq!(
+            BigFloat::from_portable(&real(1, 10)).unwrap().to_portable(),
+            real(1, 10)
+        );
+    }
+
+    #[test]
+    fn rounding_shows_in_the_round_trip() {
+        let third = rational(1, 3).to_portable();
+        let rounded = BigFloat::from_portable(&third).unwrap();
+        assert_ne!(rounded.to_portable(), third);
+        let big = Integer::from(BigInt::from(3).pow(50_u32)).to_portable();
+        assert_ne!(f64::from_portable(&big).unwrap().to_portable(), big);
+    }
+
+    #[test]
+    fn unrepresentable_values_are_rejected() {
+        assert_eq!(Integer::from_portable(&real(5, 2)), None);
+        assert_eq!(
+            Integer::from_portable(&real(-4, 1)),
+            Some(Integer::from(-4))
+        );
+        assert_eq!(
+            f64::from_portable(&PortableValue::Complex(Complex64::i())),
+            None
+        );
+        assert_eq!(
+            super::Rational::from_portable(&f64::INFINITY.to_portable()),
+            None
+        );
+        assert_eq!(
+            BigFloat::from_portable(&real(3, 2)),
+            Some(BigFloat::from_f64(1.5))
+        );
+    }
+}
+
+mod literals {
+    use num_bigfloat::BigFloat;
+    use num_complex::Complex64;
+
+    use crate::evaluator::{Evaluator, error::EvaluatorError};
+    use crate::lexer::Lexer;
+    use crate::lexer::token::{Literal, Token};
+    use crate::numeric::complex::ComplexBuiltins;
+    use crate::numeric::float::FloatBuiltins;
+    use crate::numeric::integer::Integer;
+    use crate::numeric::rational::Rational;
+    use crate::numeric::{FromLiteral, NumericValue};
+    use crate::parser::error::ParserError;
+
+    use super::rational;
+
+    fn literal(digits: &str, radix: u32, exponent: i64) -> Literal {
+        Literal {
+            digits: digits.into(),
+            radix,
+            exponent,
+            imaginary: false,
+        }
+    }
+
+    /// Reads the single number literal in `input`.
+    fn read<N: NumericValue>(input: &str) -> Result<N, String> {
+        match Lexer::new(input).next() {
+            Some(Ok(token)) => match token.node {
+                Token::Number(literal) => N::from_literal(&literal),
+                token => panic!("`{input}` is not a number: {token:?}"),
+            },
+            token => panic!("`{input}` is not a number: {token:?}"),
+        }
+    }
+
+    #[test]
+    fn rationals_are_exact() {
+        assert_eq!(read("0.1"), Ok(rational(1, 10)));
+        assert_eq!(read("1.5e-3"), Ok(rational(3, 2000)));
+        assert_eq!(read("2.5e3"), Ok(rational(2500, 1)));
+        assert_eq!(
+            read::<Rational>("0x1_0000_0000_0000_0000")
+                .unwrap()
+                .to_string(),
+            "18446744073709551616"
+        );
+        assert!(Rational::from_literal(&literal("1", 10, 1_000_000)).is_err());
+    }
+
+    #[test]
+    fn integers_reject_fractions() {
+        assert_eq!(read("2.5e3"), Ok(Integer::from(2500)));
+        assert_eq!(read("0b1010"), Ok(Integer::from(10)));
+        assert_eq!(
+            read::<Integer>("1e-3"),
+            Err("`1e-3` is not an integer".to_owned())
+        );
+    }
+
+    #[test]
+    fn floats() {
+        assert_eq!(read("6.02e23"), Ok(6.02e23));
+        assert_eq!(read("0xFF"), Ok(255.0));
+        assert_eq!(read("1e400"), Ok(f64::INFINITY));
+        assert_eq!(read("2.5"), Ok(Complex64::new(2.5, 0.0)));
+    }
+
+    #[test]
+    fn big_floats_keep_digits_past_f64_precision() {
+        let value = read::<BigFloat>("0.12345678901234567890123456789").unwrap();
+        assert_eq!(
+            value,
+            BigFloat::parse("1.2345678901234567890123456789e-1").unwrap()
+        );
+        let rounded = "0.12345678901234567890123456789".parse::<f64>().unwrap();
+        assert_ne!(value, BigFloat::from_f64(rounded));
+    }
+
+    #[test]
+    fn imaginary_literals_need_complex_numbers() {
+        assert_eq!(
+            Evaluator::new(ComplexBuiltins).parse("2.5i;").unwrap(),
+            Some(Complex64::new(0.0, 2.5))
+        );
+        let error = Evaluator::new(FloatBuiltins).parse("1 + 4i;").unwrap_err();
+        assert!(matches!(
+            &error,
+            EvaluatorError::ParserError(ParserError::InvalidNumber(_, span)) if span.start == 4
+        ));
+        assert_eq!(
+            std::error::Error::source(&error).unwrap().to_string(),
+            "imaginary number `4i` needs complex numbers"
+        );
+    }
+}
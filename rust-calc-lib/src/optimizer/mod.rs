@@ -0,0 +1,119 @@
+#[cfg(test)]
+mod tests;
+
+use crate::lexer::token::Operator;
+use crate::numeric::{NumericValue, from_bool};
+use crate::parser::ast::{Expression, ExpressionKind, UnaryOp};
+use crate::span::Span;
+
+/// Simplifies `expression` without changing what it evaluates to: constant subtrees are folded,
+/// identities such as `x * 1` and `x + 0` are removed, and double negations cancel out.
+///
+/// Operations that fail, such as `1 / 0`, are left in place so that evaluating the result still
+/// reports the error at its original span. Nothing that depends on the variables, results or
+/// functions of an evaluator is folded.
+pub fn optimize<N: NumericValue>(expression: Expression<N>) -> Expression<N> {
+    let span = expression.span;
+    let kind = match expression.kind {
+        ExpressionKind::Unary(op, operand) => return optimize_unary(op, optimize(*operand), span),
+        ExpressionKind::Binary(left, op, right) => {
+            return optimize_binary(optimize(*left), op, optimize(*right), span);
+        }
+        ExpressionKind::Conditional(condition, then_branch, else_branch) => {
+            let condition = optimize(*condition);
+            if let ExpressionKind::Number(value) = &condition.kind {
+                let chosen = if *value != N::zero() {
+                    then_branch
+                } else {
+                    else_branch
+                };
+                return optimize(*chosen);
+            }
+            ExpressionKind::Conditional(
+                Box::new(condition),
+                Box::new(optimize(*then_branch)),
+                Box::new(optimize(*else_branch)),
+            )
+        }
+        ExpressionKind::Call(name, arguments) => {
+            ExpressionKind::Call(name, arguments.into_iter().map(optimize).collect())
+        }
+        kind @ (ExpressionKind::Number(_)
+        | ExpressionKind::Variable(_)
+        | ExpressionKind::ResultRef(_)) => kind,
+    };
+    Expression::new(kind, span)
+}
+
+fn optimize_unary<N: NumericValue>(
+    op: UnaryOp<N>,
+    operand: Expression<N>,
+    span: Span,
+) -> Expression<N> {
+    if let ExpressionKind::Number(value) = &operand.kind
+        && let Ok(value) = op.apply(value.clone())
+    {
+        return Expression::new(ExpressionKind::Number(value), span);
+    }
+    match (op, operand.kind) {
+        (UnaryOp::Positive, kind) => Expression::new(kind, operand.span),
+        (UnaryOp::Negative, ExpressionKind::Unary(UnaryOp::Negative, inner)) => *inner,
+        // `!!x` is not `x`, it turns any non-zero value into one
+        (op, kind) => Expression::new(
+            ExpressionKind::Unary(op, Box::new(Expression::new(kind, operand.span))),
+            span,
+        ),
+    }
+}
+
+fn optimize_binary<N: NumericValue>(
+    left: Expression<N>,
+    op: Operator,
+    right: Expression<N>,
+    span: Span,
+) -> Expression<N> {
+    let number = |expression: &Expression<N>| match &expression.kind {
+        ExpressionKind::Number(value) => Some(value.clone()),
+        _ => None,
+    };
+    match (number(&left), number(&right)) {
+        (Some(a), Some(b)) => {
+            if let Ok(value) = op.apply(a, b) {
+                return Expression::new(ExpressionKind::Number(value), span);
+            }
+        }
+        // the right operand of a short-circuiting operator is never evaluated if the left one
+        // decides the result
+        (Some(a), None) if matches!(op, Operator::And | Operator::Or) => {
+            let decided = a != N::zero();
+            if decided == (op == Operator::Or) {
+                return Expression::new(ExpressionKind::Number(from_bool(decided)), span);
+            }
+        }
+        (Some(a), None) if is_identity(&a, op, Side::Left) => return right,
+        (None, Some(b)) if is_identity(&b, op, Side::Right) => return left,
+        _ => {}
+    }
+    Expression::new(
+        ExpressionKind::Binary(Box::new(left), op, Box::new(right)),
+        span,
+    )
+}
+
+/// The side of a binary operator an operand is on.
+#[derive(PartialEq)]
+enum Side {
+    Left,
+    Right,
+}
+
+/// Tests whether `value` on the `side` of `op` leaves the other operand unchanged.
+fn is_identity<N: NumericValue>(value: &N, op: Operator, side: Side) -> bool {
+    match op {
+        Operator::Plus => value.is_zero(),
+        Operator::Minus => side == Side::Right && value.is_zero(),
+        Operator::Star => value.is_one(),
+        Operator::Slash | Operator::Caret => side == Side::Right && value.is_one(),
+        _ => false,
+    }
+}
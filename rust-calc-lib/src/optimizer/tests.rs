@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+
+use crate::evaluator::Evaluator;
+use crate::numeric::{Arity, BuiltinFn};
+use crate::optimizer::optimize;
+use crate::parser::Parser;
+use crate::parser::ast::{Expression, Statement};
+
+struct TestBuiltins;
+
+impl BuiltinFn<f64> for TestBuiltins {
+    fn names(&self) -> &'static [&'static str] {
+        &["sqrt"]
+    }
+
+    fn arity(&self, name: &str) -> Option<Arity> {
+        (name == "sqrt").then_some(Arity::exact(1))
+    }
+
+    fn call(&self, name: &str, args: &[f64]) -> Result<f64, String> {
+        match (name, args) {
+            ("sqrt", [x]) => Ok(x.sqrt()),
+            _ => Err(format!("unknown function `{name}`")),
+        }
+    }
+
+    fn constants(&self) -> HashMap<String, f64> {
+        HashMap::new()
+    }
+}
+
+fn expression(input: &str) -> Expression<f64> {
+    match Parser::new(input).parse_program().unwrap().pop() {
+        Some(Statement::Expression(expression)) => expression,
+        statement => panic!("`{input}` is not an expression: {statement:?}"),
+    }
+}
+
+/// Macro to generate tests of the expression an input is optimized into
+macro_rules! optimizer_test {
+    ($name:ident, $input:expr, $expected:expr) => {
+        #[test]
+        fn $name() {
+            assert_eq!(
+                optimize(expression($input)),
+                expression($expected),
+                "Optimization mismatch in test '{}'\ninput: {}",
+                stringify!($name),
+                $input,
+            );
+        }
+    };
+}
+
+optimizer_test!(folds_constants, "2 * 3 + 4", "10");
+optimizer_test!(folds_nested_constants, "x * (2 + 3)", "x * 5");
+optimizer_test!(folds_comparisons, "1 < 2 && 3 == 3", "1");
+optimizer_test!(folds_unary_operators, "-(2 - 3) + !(1 > 2)", "2");
+optimizer_test!(folds_call_arguments, "sqrt(2 * 8)", "sqrt(16)");
+optimizer_test!(keeps_calls, "sqrt(16)", "sqrt(16)");
+optimizer_test!(removes_additive_identities, "0 + x - 0 + y", "x + y");
+optimizer_test!(
+    removes_multiplicative_identities,
+    "1 * x / 1 * y ^ 1",
+    "x * y"
+);
+optimizer_test!(keeps_zero_minus_x, "0 - x", "0 - x");
+optimizer_test!(keeps_one_over_x, "1 / x", "1 / x");
+optimizer_test!(keeps_times_zero, "x * 0", "x * 0");
+optimizer_test!(cancels_double_negation, "--x", "x");
+optimizer_test!(cancels_nested_negation, "-(-(x * 1))", "x");
+optimizer_test!(removes_unary_plus, "+x", "x");
+optimizer_test!(keeps_double_not, "!!x", "!!x");
+optimizer_test!(picks_constant_branch, "if 2 > 1 then x else y", "x");
+optimizer_test!(short_circuits_and, "0 && x", "0");
+optimizer_test!(short_circuits_or, "2 || x", "1");
+optimizer_test!(keeps_undecided_and, "1 && x", "1 && x");
+optimizer_test!(keeps_failing_operations, "x + 1 / 0", "x + 1 / 0");
+
+/// Inputs whose value must not change when they are optimized, evaluated with `x = -2.5`,
+/// `y = 4`, `n = -0` and `z = 0`.
+const PRESERVED: &[&str] = &[
+    "2 * 3 + 4",
+    "x * 1 + 0",
+    "0 + x - 0",
+    "1 * x / 1",
+    "x ^ 1",
+    "y ^ 1",
+    "--x",
+    "-(-(-x))",
+    "+-+x",
+    "!!x + !!z",
+    "!z",
+    "n + 0",
+    "n * 1",
+    "if 1 then x else 1 / z",
+    "if z then 1 / z else y",
+    "z && 1 / z",
+    "y || 1 / z",
+    "1 && x",
+    "sqrt(y * 1 + 0)",
+    "x * (y - 4) * 1 + --y",
+];
+
+#[test]
+fn optimization_preserves_values() {
+    let mut evaluator = Evaluator::new(TestBuiltins);
+    evaluator.parse("x = -2.5; y = 4; n = -0; z = 0;").unwrap();
+    for input in PRESERVED {
+        let original = evaluator.eval_expression(expression(input)).unwrap();
+        let optimized = evaluator
+            .eval_expression(optimize(expression(input)))
+            .unwrap();
+        assert_eq!(original, optimized, "value changed for input: {input}");
+    }
+}
+
+#[test]
+fn optimization_preserves_errors() {
+    let mut evaluator = Evaluator::new(TestBuiltins);
+    for input in ["1 / 0", "x * 1", "(1 / 0) * 1", "0 + sqrt()", "nope(2 * 3)"] {
+        let original = evaluator.eval_expression(expression(input)).unwrap_err();
+        let optimized = evaluator
+            .eval_expression(optimize(expression(input)))
+            .unwrap_err();
+        assert_eq!(original.to_string(), optimized.to_string());
+        assert_eq!(original.span(), optimized.span(), "input: {input}");
+    }
+}
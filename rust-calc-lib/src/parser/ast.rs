@@ -1,51 +1,120 @@
+use std::fmt::{self, Display};
 use std::marker::PhantomData;
 
-use crate::lexer::token::Operator;
-use crate::numeric::NumericValue;
-use crate::parser::error::ParserError;
+use crate::lexer::token::{Literal, Operator};
+use crate::numeric::{NumericValue, from_bool};
+use crate::span::{Span, Spanned};
+
+/// An expression node together with the span of source it was parsed from.
+///
+/// Equality ignores the span, see [`Spanned`].
+#[derive(Debug, Clone)]
+pub struct Expression<N: NumericValue> {
+    pub kind: ExpressionKind<N>,
+    pub span: Span,
+}
+
+impl<N: NumericValue> Expression<N> {
+    pub fn new(kind: ExpressionKind<N>, span: Span) -> Self {
+        Self { kind, span }
+    }
+}
+
+impl<N: NumericValue> PartialEq for Expression<N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind == other.kind
+    }
+}
+
+/// Builds an expression without a location in the source, e.g. for trees constructed by hand.
+impl<N: NumericValue> From<ExpressionKind<N>> for Expression<N> {
+    fn from(kind: ExpressionKind<N>) -> Self {
+        Self::new(kind, Span::default())
+    }
+}
 
 #[derive(Debug, Clone, PartialEq)]
-pub enum Expression<N: NumericValue> {
+pub enum ExpressionKind<N: NumericValue> {
     Number(N),
     Variable(String),
+    /// `$n`, the `n`-th result of the session, counting from one.
+    ResultRef(usize),
     Unary(UnaryOp<N>, Box<Expression<N>>),
     Binary(Box<Expression<N>>, Operator, Box<Expression<N>>),
-    Call(String, Box<Expression<N>>),
+    Call(String, Vec<Expression<N>>),
+    /// `if condition then a else b`, only the chosen branch is evaluated.
+    Conditional(Box<Expression<N>>, Box<Expression<N>>, Box<Expression<N>>),
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum UnaryOp<N: NumericValue> {
     Negative,
     Positive,
+    Not,
+    /// `n!`, written after its operand.
+    Factorial,
+    /// `x%`, a hundredth of its operand, written after it.
+    Percent,
     _Marker(PhantomData<N>),
 }
 
 impl<N: NumericValue> UnaryOp<N> {
-    pub fn apply(&self, a: N) -> N {
+    pub fn apply(&self, a: N) -> Result<N, String> {
         match self {
-            UnaryOp::Negative => N::zero() - a,
-            UnaryOp::Positive => a,
+            UnaryOp::Negative => Ok(N::zero() - a),
+            UnaryOp::Positive => Ok(a),
+            UnaryOp::Not => Ok(from_bool(a == N::zero())),
+            UnaryOp::Factorial => a.factorial(),
+            UnaryOp::Percent => Ok(a / N::from_literal(&Literal::decimal("100"))?),
             UnaryOp::_Marker(_) => unreachable!(),
         }
     }
+
+    /// Whether the operator is written after its operand.
+    pub fn is_postfix(&self) -> bool {
+        matches!(self, UnaryOp::Factorial | UnaryOp::Percent)
+    }
+
+    /// Returns the operator written as `operator` after an operand, if there is one.
+    pub fn postfix(operator: Operator) -> Option<Self> {
+        match operator {
+            Operator::Not => Some(Self::Factorial),
+            Operator::Percent => Some(Self::Percent),
+            _ => None,
+        }
+    }
+}
+
+impl<N: NumericValue> Display for UnaryOp<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            UnaryOp::Negative => "-",
+            UnaryOp::Positive => "+",
+            UnaryOp::Not | UnaryOp::Factorial => "!",
+            UnaryOp::Percent => "%",
+            UnaryOp::_Marker(_) => unreachable!(),
+        })
+    }
 }
 
 impl<N: NumericValue> TryFrom<Operator> for UnaryOp<N> {
-    type Error = ParserError<N>;
-    fn try_from(value: Operator) -> Result<UnaryOp<N>, ParserError<N>> {
+    /// The operator that has no unary form.
+    type Error = Operator;
+    fn try_from(value: Operator) -> Result<UnaryOp<N>, Operator> {
         match value {
             Operator::Plus => Ok(Self::Positive),
             Operator::Minus => Ok(Self::Negative),
-            _ => Err(ParserError::UnexpectedToken(
-                crate::lexer::token::Token::<N>::Operator(value),
-            )),
+            Operator::Not => Ok(Self::Not),
+            _ => Err(value),
         }
     }
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Statement<N: NumericValue> {
-    Assignment(String, Expression<N>),
+    Assignment(Spanned<String>, Expression<N>),
+    /// A user function definition such as `f(x, y) = x * y`: name, parameter names and body.
+    FunctionDefinition(Spanned<String>, Vec<String>, Expression<N>),
     Expression(Expression<N>),
     Empty,
 }
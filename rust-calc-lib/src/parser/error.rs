@@ -1,16 +1,54 @@
+use std::error::Error;
+use std::fmt::{self, Display};
+
 use crate::lexer::{error::LexerError, token::Token};
-use crate::numeric::NumericValue;
+use crate::span::{Span, Spanned};
 
 #[derive(Debug)]
-pub enum ParserError<N: NumericValue> {
+pub enum ParserError {
     LexerError(LexerError),
-    UnexpectedToken(Token<N>),
-    UnexpectedEnd,
-    InvalidAssignment,
+    UnexpectedToken(Spanned<Token>),
+    /// The input ended early, holds the position of the end of the input.
+    UnexpectedEnd(usize),
+    InvalidAssignment(Span),
+    /// A number literal the numeric backend cannot represent, with the reason.
+    InvalidNumber(String, Span),
 }
 
-impl<N: NumericValue> From<LexerError> for ParserError<N> {
+impl ParserError {
+    pub fn span(&self) -> Span {
+        match self {
+            ParserError::LexerError(lexer_error) => lexer_error.span(),
+            ParserError::UnexpectedToken(token) => token.span,
+            ParserError::UnexpectedEnd(end) => Span::new(*end, *end),
+            ParserError::InvalidAssignment(span) | ParserError::InvalidNumber(_, span) => *span,
+        }
+    }
+}
+
+impl From<LexerError> for ParserError {
     fn from(value: LexerError) -> Self {
         Self::LexerError(value)
     }
 }
+
+impl Display for ParserError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParserError::LexerError(_) => f.write_str("invalid token"),
+            ParserError::UnexpectedToken(token) => write!(f, "unexpected {}", token.node),
+            ParserError::UnexpectedEnd(_) => f.write_str("unexpected end of input"),
+            ParserError::InvalidAssignment(_) => f.write_str("invalid assignment target"),
+            ParserError::InvalidNumber(message, _) => f.write_str(message),
+        }
+    }
+}
+
+impl Error for ParserError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ParserError::LexerError(lexer_error) => Some(lexer_error),
+            _ => None,
+        }
+    }
+}
@@ -1,144 +1,506 @@
 pub mod ast;
 pub mod error;
+pub(crate) mod printer;
 pub mod tests;
 
-use crate::lexer::token::{Associativity, Operator, Punctuation};
+use crate::lexer::token::{Associativity, Keyword, Literal, Operator, Punctuation};
 use crate::lexer::{Lexer, token::Token};
 use crate::numeric::NumericValue;
-use crate::parser::ast::{Expression, Statement};
+use crate::parser::ast::{Expression, ExpressionKind, Statement, UnaryOp};
 use crate::parser::error::ParserError;
+use crate::span::{Span, Spanned};
 
 use std::iter::Peekable;
+use std::marker::PhantomData;
+
+/// Settings that change how input is read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ParserOptions {
+    /// Whether a product written without `*`, like `2x`, binds tighter than `*` and `/`, so that
+    /// `1 / 2x` is `1 / (2 * x)` rather than `1 / 2 * x`.
+    pub tight_implicit_multiplication: bool,
+    /// Whether a leading `-` or `+` binds tighter than `^`, as in spreadsheets, so that `-2^2`
+    /// is `(-2)^2 = 4` rather than `-(2^2) = -4`.
+    pub tight_negation: bool,
+}
 
 pub struct Parser<'a, N: NumericValue> {
-    lexer: Peekable<Lexer<'a, N>>,
+    lexer: Peekable<Lexer<'a>>,
     // current: Option<Token>,
+    input: &'a str,
+    /// The end of the last consumed token.
+    previous_end: usize,
+    /// The number of parentheses the next token is inside.
+    nesting: usize,
+    options: ParserOptions,
+    _number: PhantomData<N>,
 }
 
 impl<'a, N: NumericValue> Parser<'a, N> {
     pub fn new(input: &'a str) -> Self {
+        Self::with_options(input, ParserOptions::default())
+    }
+
+    pub fn with_options(input: &'a str, options: ParserOptions) -> Self {
         Self {
             lexer: Lexer::new(input).peekable(),
             // current: None,
+            input,
+            previous_end: 0,
+            nesting: 0,
+            options,
+            _number: PhantomData,
         }
     }
 
-    fn peek(&mut self) -> Result<Option<&Token<N>>, ParserError<N>> {
+    fn peek(&mut self) -> Result<Option<&Token>, ParserError> {
         self.lexer
             .peek()
-            .map(Result::as_ref)
+            .map(|token| token.as_ref().map(|token| &token.node))
             .transpose()
             .map_err(|e| e.clone().into())
     }
 
-    fn advance(&mut self) -> Result<Token<N>, ParserError<N>> {
-        self.lexer
+    fn advance(&mut self) -> Result<Spanned<Token>, ParserError> {
+        let token = self
+            .lexer
             .next()
-            .ok_or(ParserError::UnexpectedEnd)?
-            .map_err(Into::into)
+            .ok_or(ParserError::UnexpectedEnd(self.input.len()))??;
+        self.previous_end = token.span.end;
+        Ok(token)
+    }
+
+    /// Whether a line break separates the next token from the last consumed one. Inside
+    /// parentheses a line break is only ever whitespace, so there it never does.
+    fn next_on_new_line(&mut self) -> bool {
+        if self.nesting > 0 {
+            return false;
+        }
+        match self.lexer.peek() {
+            Some(Ok(token)) => self.input[self.previous_end..token.span.start].contains('\n'),
+            _ => false,
+        }
+    }
+
+    /// Whether the next token ends the expression before it by starting a new line with something
+    /// that could begin a statement of its own: an operand, or a `!` negating one. A line that
+    /// starts with an operator that can stand between two operands, signs included, continues
+    /// the expression, so `total = a` followed by a line `- b` assigns `a - b`.
+    fn next_starts_line(&mut self) -> Result<bool, ParserError> {
+        if !self.next_on_new_line() {
+            return Ok(false);
+        }
+        Ok(match self.peek()? {
+            Some(Token::Operator(Operator::Not)) => true,
+            Some(token) => starts_operand(token),
+            None => false,
+        })
+    }
+
+    /// Runs `parse` for the inside of a pair of parentheses.
+    fn parenthesized<T>(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<T, ParserError>,
+    ) -> Result<T, ParserError> {
+        self.nesting += 1;
+        let result = parse(self);
+        self.nesting -= 1;
+        result
     }
 
-    fn expect(&mut self, token: &Token<N>) -> Result<(), ParserError<N>> {
+    /// Consumes the next token if it is `token`, returning its span.
+    fn expect(&mut self, token: &Token) -> Result<Span, ParserError> {
         let next_token = self.advance()?;
-        if &next_token == token {
-            Ok(())
+        if &next_token.node == token {
+            Ok(next_token.span)
         } else {
             Err(ParserError::UnexpectedToken(next_token))
         }
     }
 
-    pub fn parse_program(&mut self) -> Result<Vec<Statement<N>>, ParserError<N>> {
+    /// Parses statements separated by `;` or line breaks. The `;` after the last statement may be
+    /// left out. A line that starts with a binary operator, `-` and `+` included, continues the
+    /// statement before it rather than starting one of its own.
+    pub fn parse_program(&mut self) -> Result<Vec<Statement<N>>, ParserError> {
         let mut statements = Vec::new();
-        while self.peek()?.is_some() {
-            let statement = self.parse_statement()?;
-            if statement != Statement::Empty {
+        while let Some(statement) = self.next_statement() {
+            statements.push(statement?);
+        }
+        Ok(statements)
+    }
+
+    /// Parses the next statement together with the `;` or line break ending it, or returns `None`
+    /// at the end of the input. After an error the rest of the statement is skipped, so that
+    /// parsing can go on with the next one.
+    pub fn next_statement(&mut self) -> Option<Result<Statement<N>, ParserError>> {
+        if matches!(self.peek(), Ok(None)) {
+            return None;
+        }
+        let statement = self.parse_statement().and_then(|statement| {
+            if statement != Statement::Empty && self.peek()?.is_some() && !self.next_on_new_line() {
                 self.expect(&Token::Punctuation(Punctuation::Semicolon))?;
             }
-            statements.push(statement);
+            Ok(statement)
+        });
+        if statement.is_err() {
+            self.skip_statement();
+        }
+        Some(statement)
+    }
+
+    /// Skips the tokens up to the end of the statement that failed to parse: past the next `;`,
+    /// or up to the next line that starts a statement of its own, see
+    /// [`Self::next_starts_line`].
+    fn skip_statement(&mut self) {
+        let mut nesting = std::mem::take(&mut self.nesting);
+        // the failing token may have been the `;` itself
+        if nesting == 0 && self.input[..self.previous_end].ends_with(';') {
+            return;
+        }
+        while nesting > 0 || !self.next_starts_line().unwrap_or(false) {
+            // the lexer cannot go on after an error either
+            let Some(Ok(token)) = self.lexer.next() else {
+                return;
+            };
+            self.previous_end = token.span.end;
+            match token.node {
+                Token::Punctuation(Punctuation::Semicolon) if nesting == 0 => return,
+                Token::Punctuation(Punctuation::LeftParenthesis) => nesting += 1,
+                Token::Punctuation(Punctuation::RightParenthesis) => {
+                    nesting = nesting.saturating_sub(1)
+                }
+                _ => {}
+            }
         }
-        Ok(statements)
     }
 
-    fn parse_statement(&mut self) -> Result<Statement<N>, ParserError<N>> {
-        match self.advance()? {
+    fn parse_statement(&mut self) -> Result<Statement<N>, ParserError> {
+        let first = self.advance()?;
+        match first.node {
             Token::Identifier(var)
                 if matches!(
                     self.peek()?,
                     Some(&Token::Punctuation(Punctuation::Assignment))
                 ) =>
             {
-                self.parse_assignment(var)
+                self.parse_assignment(Spanned::new(var, first.span))
             }
             Token::Punctuation(Punctuation::Semicolon) => Ok(Statement::Empty),
-            token => self.parse_expression(token, 0).map(Statement::Expression),
+            node => {
+                let token = Spanned::new(node, first.span);
+                let expression = self.parse_expression(token, 0)?;
+                if matches!(
+                    self.peek()?,
+                    Some(&Token::Punctuation(Punctuation::Assignment))
+                ) {
+                    return self.parse_function_definition(expression);
+                }
+                Ok(Statement::Expression(expression))
+            }
+        }
+    }
+
+    /// Turns an already parsed call such as `f(x, y)` followed by `=` into a function definition.
+    /// Every argument of the call has to be a distinct variable name.
+    fn parse_function_definition(
+        &mut self,
+        signature: Expression<N>,
+    ) -> Result<Statement<N>, ParserError> {
+        let ExpressionKind::Call(func_name, arguments) = signature.kind else {
+            return Err(ParserError::InvalidAssignment(signature.span));
+        };
+        let mut parameters = Vec::with_capacity(arguments.len());
+        for argument in arguments {
+            match argument.kind {
+                ExpressionKind::Variable(parameter) if !parameters.contains(&parameter) => {
+                    parameters.push(parameter)
+                }
+                _ => return Err(ParserError::InvalidAssignment(argument.span)),
+            }
         }
+        self.expect(&Token::Punctuation(Punctuation::Assignment))?;
+        let first_body_token = self.advance()?;
+        // the name span covers the whole signature, `f(x, y)`
+        Ok(Statement::FunctionDefinition(
+            Spanned::new(func_name, signature.span),
+            parameters,
+            self.parse_expression(first_body_token, 0)?,
+        ))
     }
 
-    fn parse_assignment(&mut self, var_name: String) -> Result<Statement<N>, ParserError<N>> {
+    fn parse_assignment(&mut self, var_name: Spanned<String>) -> Result<Statement<N>, ParserError> {
         self.expect(&Token::Punctuation(Punctuation::Assignment))?;
         let first_expression_token = self.advance()?;
         Ok(Statement::Assignment(
-            var_name.clone(),
+            var_name,
             self.parse_expression(first_expression_token, 0)?,
         ))
     }
 
+    /// Parses an expression by precedence climbing, `min_precedence` being the lowest
+    /// [`binding_power`] of the operators it may contain.
     fn parse_expression(
         &mut self,
-        first: Token<N>,
+        first: Spanned<Token>,
         min_precedence: u8,
-    ) -> Result<Expression<N>, ParserError<N>> {
-        let mut primary = self.parse_primary(first)?;
+    ) -> Result<Expression<N>, ParserError> {
+        let prefix = match first.node {
+            Token::Operator(operator) => UnaryOp::try_from(operator).ok(),
+            _ => None,
+        };
+        let mut primary = match prefix {
+            Some(op) => self.parse_prefix(op, first.span)?,
+            None => self.parse_postfix(first)?,
+        };
         loop {
-            match self.peek()? {
-                Some(&Token::Operator(operator)) => {
-                    if operator.priority() < min_precedence {
-                        break;
-                    }
-                    self.advance()?; // consume the operator
-                    let next_min_prec = if operator.associativity() == Associativity::Left {
-                        operator.priority() + 1
-                    } else {
-                        operator.priority()
-                    };
-                    let token_after_operator = self.advance()?;
-                    let after_operator =
-                        self.parse_expression(token_after_operator, next_min_prec)?;
-                    primary =
-                        Expression::Binary(Box::new(primary), operator, Box::new(after_operator));
+            if self.next_starts_line()? {
+                break;
+            }
+            let line_break = self.next_on_new_line();
+            let (operator, precedence) = match self.peek()? {
+                // a `%` left over by `parse_postfix` is a remainder
+                Some(&Token::Operator(Operator::Percent)) => {
+                    (Operator::Rem, binding_power(Operator::Rem))
+                }
+                Some(&Token::Operator(operator)) if operator.is_binary() => {
+                    (operator, binding_power(operator))
+                }
+                // an operand right after another one on the same line, as in `2x` or
+                // `(a + b)(a - b)`
+                Some(
+                    Token::Number(_)
+                    | Token::Identifier(_)
+                    | Token::ResultRef(_)
+                    | Token::Punctuation(Punctuation::LeftParenthesis),
+                ) if !line_break => {
+                    let tighter = self.options.tight_implicit_multiplication;
+                    (
+                        Operator::Star,
+                        binding_power(Operator::Star) + tighter as u8,
+                    )
                 }
                 Some(&Token::Punctuation(Punctuation::Semicolon)) => break,
                 Some(&Token::Punctuation(Punctuation::RightParenthesis)) => break,
+                Some(&Token::Punctuation(Punctuation::Comma)) => break,
+                Some(&Token::Punctuation(Punctuation::Assignment)) => break,
+                Some(&Token::Keyword(Keyword::Then | Keyword::Else)) => break,
                 None => break,
-                Some(token) => return Err(ParserError::UnexpectedToken(token.clone())),
+                Some(_) => return Err(ParserError::UnexpectedToken(self.advance()?)),
+            };
+            if precedence < min_precedence {
+                break;
             }
+            if matches!(self.peek()?, Some(Token::Operator(_))) {
+                self.advance()?; // consume the operator
+            }
+            let next_min_prec = if operator.associativity() == Associativity::Left {
+                precedence + 1
+            } else {
+                precedence
+            };
+            let token_after_operator = self.advance()?;
+            let after_operator = self.parse_expression(token_after_operator, next_min_prec)?;
+            let span = primary.span.to(after_operator.span);
+            primary = Expression::new(
+                ExpressionKind::Binary(Box::new(primary), operator, Box::new(after_operator)),
+                span,
+            );
         }
         Ok(primary)
     }
 
-    fn parse_primary(&mut self, first: Token<N>) -> Result<Expression<N>, ParserError<N>> {
-        match first {
-            Token::Number(num) => Ok(Expression::Number(num)),
-            Token::Identifier(var_name) => match self.peek()? {
-                Some(&Token::Punctuation(Punctuation::LeftParenthesis)) => {
-                    let left_parenthesis = self.advance()?;
-                    let argument = self.parse_primary(left_parenthesis)?;
-                    Ok(Expression::Call(var_name, Box::new(argument)))
+    /// Parses the operand of the prefix operator `op` at `start`, with the operators binding
+    /// at least as tightly as [`operand_binding_power`] says.
+    fn parse_prefix(&mut self, op: UnaryOp<N>, start: Span) -> Result<Expression<N>, ParserError> {
+        let next_token = self.advance()?;
+        let operand =
+            self.parse_expression(next_token, operand_binding_power(&op, self.options))?;
+        let span = start.to(operand.span);
+        Ok(Expression::new(
+            ExpressionKind::Unary(op, Box::new(operand)),
+            span,
+        ))
+    }
+
+    /// Parses a primary followed by any number of postfix operators, `!` for the factorial and
+    /// `%` for a percentage. A `%` that is a remainder, see [`Self::remainder_next`], is left
+    /// alone.
+    fn parse_postfix(&mut self, first: Spanned<Token>) -> Result<Expression<N>, ParserError> {
+        let mut operand = self.parse_primary(first)?;
+        while !self.next_starts_line()?
+            && let Some(&Token::Operator(operator)) = self.peek()?
+        {
+            let Some(op) = UnaryOp::postfix(operator) else {
+                break;
+            };
+            if op == UnaryOp::Percent && self.remainder_next() {
+                break;
+            }
+            let end = self.advance()?.span;
+            let span = operand.span.to(end);
+            operand = Expression::new(ExpressionKind::Unary(op, Box::new(operand)), span);
+        }
+        Ok(operand)
+    }
+
+    /// Whether the `%` up next is the remainder rather than a percentage. It is if an operand
+    /// follows, as in `7 % 3`, or if it stands apart on both sides and is followed by a sign
+    /// written against its operand, as in `7 % -3`. `50% - 5` and `50% -5` subtract from a
+    /// percentage.
+    fn remainder_next(&self) -> bool {
+        let mut lexer = self.lexer.clone();
+        let mut next = || lexer.next().and_then(Result::ok);
+        let (Some(percent), Some(second)) = (next(), next()) else {
+            return false;
+        };
+        if starts_operand(&second.node) {
+            return true;
+        }
+        if !matches!(
+            second.node,
+            Token::Operator(Operator::Minus | Operator::Plus)
+        ) {
+            return false;
+        }
+        next().is_some_and(|operand| {
+            starts_operand(&operand.node)
+                && self.previous_end < percent.span.start
+                && percent.span.end < second.span.start
+                && second.span.end == operand.span.start
+        })
+    }
+
+    fn parse_primary(&mut self, first: Spanned<Token>) -> Result<Expression<N>, ParserError> {
+        let span = first.span;
+        match first.node {
+            Token::Number(literal) => Ok(Expression::new(
+                ExpressionKind::Number(number(&literal, span)?),
+                span,
+            )),
+            Token::ResultRef(number) => {
+                Ok(Expression::new(ExpressionKind::ResultRef(number), span))
+            }
+            Token::Identifier(var_name) => {
+                let call = matches!(
+                    self.peek()?,
+                    Some(&Token::Punctuation(Punctuation::LeftParenthesis))
+                ) && !self.next_on_new_line();
+                if !call {
+                    return Ok(Expression::new(ExpressionKind::Variable(var_name), span));
                 }
-                _ => Ok(Expression::Variable(var_name)),
-            },
+                self.advance()?; // consume the left parenthesis
+                let (arguments, end) = self.parenthesized(Self::parse_arguments)?;
+                Ok(Expression::new(
+                    ExpressionKind::Call(var_name, arguments),
+                    span.to(end),
+                ))
+            }
             Token::Punctuation(Punctuation::LeftParenthesis) => {
-                let next_token = self.advance()?;
-                let result = self.parse_expression(next_token, 0)?;
-                self.expect(&Token::Punctuation(Punctuation::RightParenthesis))?;
-                Ok(result)
+                let result = self.parenthesized(|parser| {
+                    let next_token = parser.advance()?;
+                    parser.parse_expression(next_token, 0)
+                })?;
+                let end = self.expect(&Token::Punctuation(Punctuation::RightParenthesis))?;
+                Ok(Expression::new(result.kind, span.to(end)))
             }
-            Token::Operator(operator @ (Operator::Plus | Operator::Minus)) => {
-                let next_token = self.advance()?;
-                let operand = self.parse_primary(next_token)?;
-                Ok(Expression::Unary(operator.try_into()?, Box::new(operand)))
+            Token::Keyword(Keyword::If) => self.parse_conditional(span),
+            node => Err(ParserError::UnexpectedToken(Spanned::new(node, span))),
+        }
+    }
+
+    /// Parses `condition then a else b`, expecting the `if` keyword at `start` to be already
+    /// consumed.
+    fn parse_conditional(&mut self, start: Span) -> Result<Expression<N>, ParserError> {
+        let first_condition_token = self.advance()?;
+        let condition = self.parse_expression(first_condition_token, 0)?;
+        self.expect(&Token::Keyword(Keyword::Then))?;
+        let first_then_token = self.advance()?;
+        let then_branch = self.parse_expression(first_then_token, 0)?;
+        self.expect(&Token::Keyword(Keyword::Else))?;
+        let first_else_token = self.advance()?;
+        let else_branch = self.parse_expression(first_else_token, 0)?;
+        let span = start.to(else_branch.span);
+        Ok(Expression::new(
+            ExpressionKind::Conditional(
+                Box::new(condition),
+                Box::new(then_branch),
+                Box::new(else_branch),
+            ),
+            span,
+        ))
+    }
+
+    /// Parses a comma separated argument list, expecting the opening parenthesis
+    /// to be already consumed. Consumes the closing parenthesis and returns its span.
+    fn parse_arguments(&mut self) -> Result<(Vec<Expression<N>>, Span), ParserError> {
+        let mut arguments = Vec::new();
+        if matches!(
+            self.peek()?,
+            Some(&Token::Punctuation(Punctuation::RightParenthesis))
+        ) {
+            let end = self.advance()?.span;
+            return Ok((arguments, end));
+        }
+        loop {
+            let first_argument_token = self.advance()?;
+            arguments.push(self.parse_expression(first_argument_token, 0)?);
+            let separator = self.advance()?;
+            match separator.node {
+                Token::Punctuation(Punctuation::Comma) => continue,
+                Token::Punctuation(Punctuation::RightParenthesis) => {
+                    return Ok((arguments, separator.span));
+                }
+                _ => return Err(ParserError::UnexpectedToken(separator)),
             }
-            token => Err(ParserError::UnexpectedToken(token)),
         }
     }
 }
+
+/// Whether `token` is the first token of an operand, not counting prefix operators.
+fn starts_operand(token: &Token) -> bool {
+    matches!(
+        token,
+        Token::Number(_)
+            | Token::Identifier(_)
+            | Token::ResultRef(_)
+            | Token::Keyword(Keyword::If)
+            | Token::Punctuation(Punctuation::LeftParenthesis)
+    )
+}
+
+/// How tightly a binary operator binds in [`Parser::parse_expression`]. This is its
+/// [`Operator::priority`] doubled, which leaves a level between any two priorities for the
+/// settings of [`ParserOptions`] and for a sign, which binds between `*` and `^`.
+pub(crate) fn binding_power(operator: Operator) -> u8 {
+    operator.priority() * 2
+}
+
+/// The lowest [`binding_power`] of the operators in the operand of the prefix operator `op`.
+/// A sign takes in `^`, unless [`ParserOptions::tight_negation`] is set, so `-x ^ 2` is
+/// `-(x ^ 2)` while `-x * y` is `(-x) * y`. `!` takes in no binary operator at all.
+pub(crate) fn operand_binding_power<N: NumericValue>(
+    op: &UnaryOp<N>,
+    options: ParserOptions,
+) -> u8 {
+    match op {
+        UnaryOp::Negative | UnaryOp::Positive if !options.tight_negation => {
+            binding_power(Operator::Caret)
+        }
+        _ => binding_power(Operator::Not),
+    }
+}
+
+/// The value of a number literal in the numeric backend, imaginary if it has the `i` suffix.
+fn number<N: NumericValue>(literal: &Literal, span: Span) -> Result<N, ParserError> {
+    let invalid = |message| ParserError::InvalidNumber(message, span);
+    let value = N::from_literal(literal).map_err(invalid)?;
+    if !literal.imaginary {
+        return Ok(value);
+    }
+    match N::imaginary_unit() {
+        Some(i) => Ok(value * i),
+        None => Err(invalid(format!(
+            "imaginary number `{literal}` needs complex numbers"
+        ))),
+    }
+}
@@ -0,0 +1,172 @@
+use std::fmt::{self, Display};
+
+use crate::format::{FormatOptions, Notation, PrettyDisplay};
+use crate::lexer::token::{Associativity, Operator};
+use crate::numeric::NumericValue;
+use crate::parser::ast::{Expression, ExpressionKind, Statement, UnaryOp};
+use crate::parser::{ParserOptions, binding_power, operand_binding_power};
+
+/// How tightly an expression binds, compared against the [`binding_power`] of operators.
+/// Postfix unary operators apply to a single primary and so bind tighter than any other
+/// operator, so that `-3!` is `-(3!)`.
+pub(crate) const POSTFIX: u8 = u8::MAX - 1;
+/// Names, calls and plain literals, which never need parentheses.
+pub(crate) const ATOM: u8 = u8::MAX;
+/// Conditionals, whose `else` branch extends as far to the right as possible.
+const CONDITIONAL: u8 = 0;
+
+/// Writes the expression back as source text with only the parentheses needed to parse into the
+/// same tree again with the default [`ParserOptions`], and single spaces around binary
+/// operators, e.g. `2 * (x + 1) ^ 2`.
+impl<N: NumericValue + PrettyDisplay> Display for Expression<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            ExpressionKind::Number(value) => f.write_str(&number(value)),
+            ExpressionKind::Variable(name) => f.write_str(name),
+            ExpressionKind::ResultRef(number) => write!(f, "${number}"),
+            ExpressionKind::Unary(op, operand) if op.is_postfix() => {
+                operand_fmt(f, operand, precedence(operand) < POSTFIX)?;
+                write!(f, "{op}")
+            }
+            ExpressionKind::Unary(op, operand) => {
+                write!(f, "{op}")?;
+                operand_fmt(f, operand, prefix_operand_needs_parentheses(op, operand))
+            }
+            ExpressionKind::Binary(left, op, right) => {
+                operand_fmt(f, left, needs_parentheses(left, *op, Side::Left))?;
+                write!(f, " {op} ")?;
+                operand_fmt(f, right, needs_parentheses(right, *op, Side::Right))
+            }
+            ExpressionKind::Call(name, arguments) => {
+                write!(f, "{name}(")?;
+                for (i, argument) in arguments.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{argument}")?;
+                }
+                f.write_str(")")
+            }
+            ExpressionKind::Conditional(condition, then_branch, else_branch) => {
+                write!(f, "if {condition} then {then_branch} else {else_branch}")
+            }
+        }
+    }
+}
+
+/// Writes the statement without a trailing semicolon.
+impl<N: NumericValue + PrettyDisplay> Display for Statement<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Statement::Assignment(name, value) => write!(f, "{} = {value}", name.node),
+            Statement::FunctionDefinition(name, parameters, body) => {
+                write!(f, "{}({}) = {body}", name.node, parameters.join(", "))
+            }
+            Statement::Expression(expression) => write!(f, "{expression}"),
+            Statement::Empty => Ok(()),
+        }
+    }
+}
+
+/// The side of a binary operator an operand is on.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Side {
+    Left,
+    Right,
+}
+
+/// Whether `operand` has to be parenthesized on the `side` of `op` to parse as its operand.
+pub(crate) fn needs_parentheses<N: NumericValue + PrettyDisplay>(
+    operand: &Expression<N>,
+    op: Operator,
+    side: Side,
+) -> bool {
+    // an operand starting with a prefix operator, as in `2 ^ -1`, ends where it has to by itself
+    if side == Side::Right && starts_with_prefix(operand) {
+        return false;
+    }
+    let (precedence, power) = (precedence(operand), binding_power(op));
+    let inner_side = match op.associativity() {
+        Associativity::Left => Side::Left,
+        Associativity::Right => Side::Right,
+    };
+    precedence < power || precedence == power && side != inner_side
+}
+
+/// Whether `operand` has to be parenthesized after the prefix operator `op` to parse as its
+/// operand.
+pub(crate) fn prefix_operand_needs_parentheses<N: NumericValue + PrettyDisplay>(
+    op: &UnaryOp<N>,
+    operand: &Expression<N>,
+) -> bool {
+    // prefix operators in a row, as in `--x`, apply from right to left whatever they bind
+    !starts_with_prefix(operand)
+        && precedence(operand) < operand_binding_power(op, ParserOptions::default())
+}
+
+/// How tightly a prefix operator binds: looser than the operators it takes in, and tighter
+/// than the ones that take it in, e.g. between `*` and `^` for `-`.
+fn prefix_precedence<N: NumericValue>(op: &UnaryOp<N>) -> u8 {
+    operand_binding_power(op, ParserOptions::default()) - 1
+}
+
+/// Whether `expression` is written starting with a prefix operator, like `-x` or the number
+/// `-2`.
+fn starts_with_prefix<N: NumericValue + PrettyDisplay>(expression: &Expression<N>) -> bool {
+    match &expression.kind {
+        ExpressionKind::Unary(op, _) => !op.is_postfix(),
+        ExpressionKind::Number(_) => {
+            precedence(expression) == prefix_precedence(&UnaryOp::<N>::Negative)
+        }
+        _ => false,
+    }
+}
+
+fn operand_fmt<N: NumericValue + PrettyDisplay>(
+    f: &mut fmt::Formatter<'_>,
+    operand: &Expression<N>,
+    parenthesize: bool,
+) -> fmt::Result {
+    match parenthesize {
+        true => write!(f, "({operand})"),
+        false => write!(f, "{operand}"),
+    }
+}
+
+/// Writes every digit in fixed notation, so that the literal reads back as the same value.
+pub(crate) fn number<N: PrettyDisplay>(value: &N) -> String {
+    value.pretty(&FormatOptions {
+        notation: Notation::Fixed,
+        ..FormatOptions::default()
+    })
+}
+
+pub(crate) fn precedence<N: NumericValue + PrettyDisplay>(expression: &Expression<N>) -> u8 {
+    match &expression.kind {
+        // values computed by the optimizer may be written with operators of their own, like
+        // the `-` of `-2`, the `/` of `1/3` or the `+` of `1 + 2i`
+        ExpressionKind::Number(value) => {
+            let text = number(value);
+            if text[1..].contains(['+', '-']) {
+                binding_power(Operator::Plus)
+            } else if text.contains('/') {
+                binding_power(Operator::Slash)
+            } else if text.starts_with('-') {
+                prefix_precedence(&UnaryOp::<N>::Negative)
+            } else {
+                ATOM
+            }
+        }
+        ExpressionKind::Variable(_) | ExpressionKind::ResultRef(_) | ExpressionKind::Call(_, _) => {
+            ATOM
+        }
+        ExpressionKind::Unary(op, _) if op.is_postfix() => POSTFIX,
+        // `!-x ^ 2` is `!(-(x ^ 2))`, prefix operators in a row bind as loosely as the loosest
+        ExpressionKind::Unary(op, operand) if starts_with_prefix(operand) => {
+            prefix_precedence(op).min(precedence(operand))
+        }
+        ExpressionKind::Unary(op, _) => prefix_precedence(op),
+        ExpressionKind::Binary(_, op, _) => binding_power(*op),
+        ExpressionKind::Conditional(_, _, _) => CONDITIONAL,
+    }
+}
@@ -1,6 +1,14 @@
 #![allow(unused_imports)]
 use crate::lexer::token::Operator;
-use crate::parser::{Expression, Statement, ast::UnaryOp};
+use crate::parser::{
+    Expression, Statement,
+    ast::{ExpressionKind, UnaryOp},
+};
+use crate::span::{Span, Spanned};
+
+fn name(name: &str) -> Spanned<String> {
+    Spanned::new(name.to_string(), Span::default())
+}
 
 /// Macro to generate lexer tests
 macro_rules! lexer_test {
@@ -31,139 +39,734 @@ macro_rules! lexer_test {
 lexer_test!(
     number_literal,
     "4;",
-    [Statement::Expression(Expression::Number(4f64)),]
+    [Statement::Expression(ExpressionKind::Number(4f64).into()),]
 );
 
 lexer_test!(
     variable_expression,
     "x;",
-    [Statement::Expression(Expression::Variable("x".to_string())),]
+    [Statement::Expression(
+        ExpressionKind::Variable("x".to_string()).into()
+    ),]
 );
 
 lexer_test!(
     unary_negative,
     "-4;",
-    [Statement::Expression(Expression::Unary(
-        UnaryOp::Negative,
-        Box::new(Expression::Number(4f64))
-    )),]
+    [Statement::Expression(
+        ExpressionKind::Unary(
+            UnaryOp::Negative,
+            Box::new(ExpressionKind::Number(4f64).into())
+        )
+        .into()
+    ),]
 );
 
 lexer_test!(
     unary_positive,
     "+5;",
-    [Statement::Expression(Expression::Unary(
-        UnaryOp::Positive,
-        Box::new(Expression::Number(5f64))
-    )),]
+    [Statement::Expression(
+        ExpressionKind::Unary(
+            UnaryOp::Positive,
+            Box::new(ExpressionKind::Number(5f64).into())
+        )
+        .into()
+    ),]
 );
 
 lexer_test!(
     binary_addition,
     "2 + 3;",
-    [Statement::Expression(Expression::Binary(
-        Box::new(Expression::Number(2f64)),
-        Operator::Plus,
-        Box::new(Expression::Number(3f64))
-    )),]
+    [Statement::Expression(
+        ExpressionKind::Binary(
+            Box::new(ExpressionKind::Number(2f64).into()),
+            Operator::Plus,
+            Box::new(ExpressionKind::Number(3f64).into())
+        )
+        .into()
+    ),]
 );
 
 lexer_test!(
     binary_multiplication,
     "4 * 5;",
-    [Statement::Expression(Expression::Binary(
-        Box::new(Expression::Number(4f64)),
-        Operator::Star,
-        Box::new(Expression::Number(5f64))
-    )),]
+    [Statement::Expression(
+        ExpressionKind::Binary(
+            Box::new(ExpressionKind::Number(4f64).into()),
+            Operator::Star,
+            Box::new(ExpressionKind::Number(5f64).into())
+        )
+        .into()
+    ),]
 );
 
 lexer_test!(
     precedence_test,
     "2 + 3 * 4;",
-    [Statement::Expression(Expression::Binary(
-        Box::new(Expression::Number(2f64)),
-        Operator::Plus,
-        Box::new(Expression::Binary(
-            Box::new(Expression::Number(3f64)),
-            Operator::Star,
-            Box::new(Expression::Number(4f64))
-        ))
-    )),]
+    [Statement::Expression(
+        ExpressionKind::Binary(
+            Box::new(ExpressionKind::Number(2f64).into()),
+            Operator::Plus,
+            Box::new(
+                ExpressionKind::Binary(
+                    Box::new(ExpressionKind::Number(3f64).into()),
+                    Operator::Star,
+                    Box::new(ExpressionKind::Number(4f64).into())
+                )
+                .into()
+            )
+        )
+        .into()
+    ),]
 );
 
 lexer_test!(
     parenthesis_test,
     "(2 + 3) * 4;",
-    [Statement::Expression(Expression::Binary(
-        Box::new(Expression::Binary(
-            Box::new(Expression::Number(2f64)),
-            Operator::Plus,
-            Box::new(Expression::Number(3f64))
-        )),
-        Operator::Star,
-        Box::new(Expression::Number(4f64))
-    )),]
+    [Statement::Expression(
+        ExpressionKind::Binary(
+            Box::new(
+                ExpressionKind::Binary(
+                    Box::new(ExpressionKind::Number(2f64).into()),
+                    Operator::Plus,
+                    Box::new(ExpressionKind::Number(3f64).into())
+                )
+                .into()
+            ),
+            Operator::Star,
+            Box::new(ExpressionKind::Number(4f64).into())
+        )
+        .into()
+    ),]
 );
 
 lexer_test!(
     assignment_statement,
     "x = 42;",
     [Statement::Assignment(
-        "x".to_string(),
-        Expression::Number(42f64)
+        name("x"),
+        ExpressionKind::Number(42f64).into()
     ),]
 );
 
 lexer_test!(
     chained_add_sub,
     "1 + 2 - 3;",
-    [Statement::Expression(Expression::Binary(
-        Box::new(Expression::Binary(
-            Box::new(Expression::Number(1f64)),
-            Operator::Plus,
-            Box::new(Expression::Number(2f64))
-        )),
-        Operator::Minus,
-        Box::new(Expression::Number(3f64))
-    )),]
+    [Statement::Expression(
+        ExpressionKind::Binary(
+            Box::new(
+                ExpressionKind::Binary(
+                    Box::new(ExpressionKind::Number(1f64).into()),
+                    Operator::Plus,
+                    Box::new(ExpressionKind::Number(2f64).into())
+                )
+                .into()
+            ),
+            Operator::Minus,
+            Box::new(ExpressionKind::Number(3f64).into())
+        )
+        .into()
+    ),]
 );
 
 lexer_test!(
     function_call_simple,
     "square(2);",
-    [Statement::Expression(Expression::Call(
-        "square".to_string(),
-        Box::new(Expression::Number(2f64))
-    )),]
+    [Statement::Expression(
+        ExpressionKind::Call(
+            "square".to_string(),
+            vec![ExpressionKind::Number(2f64).into()]
+        )
+        .into()
+    ),]
 );
 
 lexer_test!(
     nested_function_call,
     "f(g(1));",
-    [Statement::Expression(Expression::Call(
-        "f".to_string(),
-        Box::new(Expression::Call(
-            "g".to_string(),
-            Box::new(Expression::Number(1f64))
-        ))
-    )),]
+    [Statement::Expression(
+        ExpressionKind::Call(
+            "f".to_string(),
+            vec![
+                ExpressionKind::Call("g".to_string(), vec![ExpressionKind::Number(1f64).into()])
+                    .into()
+            ]
+        )
+        .into()
+    ),]
+);
+
+lexer_test!(
+    function_call_multiple_arguments,
+    "max(1, x + 2, 3);",
+    [Statement::Expression(
+        ExpressionKind::Call(
+            "max".to_string(),
+            vec![
+                ExpressionKind::Number(1f64).into(),
+                ExpressionKind::Binary(
+                    Box::new(ExpressionKind::Variable("x".to_string()).into()),
+                    Operator::Plus,
+                    Box::new(ExpressionKind::Number(2f64).into())
+                )
+                .into(),
+                ExpressionKind::Number(3f64).into()
+            ]
+        )
+        .into()
+    ),]
 );
 
+lexer_test!(
+    function_call_no_arguments,
+    "rand();",
+    [Statement::Expression(
+        ExpressionKind::Call("rand".to_string(), vec![]).into()
+    ),]
+);
+
+lexer_test!(
+    function_definition,
+    "f(x, y) = x * y;",
+    [Statement::FunctionDefinition(
+        name("f"),
+        vec!["x".to_string(), "y".to_string()],
+        ExpressionKind::Binary(
+            Box::new(ExpressionKind::Variable("x".to_string()).into()),
+            Operator::Star,
+            Box::new(ExpressionKind::Variable("y".to_string()).into())
+        )
+        .into()
+    ),]
+);
+
+#[test]
+fn function_definition_requires_variable_parameters() {
+    for input in ["f(1) = 2;", "f(x, x) = x;", "2 + 3 = 5;"] {
+        let mut parser = crate::parser::Parser::<f64>::new(input);
+        assert!(
+            matches!(
+                parser.parse_program(),
+                Err(crate::parser::error::ParserError::InvalidAssignment(_))
+            ),
+            "input: {input}"
+        );
+    }
+}
+
 lexer_test!(empty_statement, ";", [Statement::Empty,]);
 
 lexer_test!(
     multiple_statements,
     "x = 1; y = x + 2;",
     [
-        Statement::Assignment("x".to_string(), Expression::Number(1f64)),
+        Statement::Assignment(name("x"), ExpressionKind::Number(1f64).into()),
         Statement::Assignment(
-            "y".to_string(),
-            Expression::Binary(
-                Box::new(Expression::Variable("x".to_string())),
+            name("y"),
+            ExpressionKind::Binary(
+                Box::new(ExpressionKind::Variable("x".to_string()).into()),
                 Operator::Plus,
-                Box::new(Expression::Number(2f64))
+                Box::new(ExpressionKind::Number(2f64).into())
             )
+            .into()
         ),
     ]
 );
+
+#[test]
+fn expression_spans() {
+    let mut parser = crate::parser::Parser::<f64>::new("x = -(1 + y) * max(2, 3);");
+    let Statement::Assignment(var_name, expression) = parser.parse_program().unwrap().remove(0)
+    else {
+        panic!("expected an assignment");
+    };
+    assert_eq!(var_name.span, Span::new(0, 1));
+    assert_eq!(expression.span, Span::new(4, 24));
+    let ExpressionKind::Binary(negation, _, call) = expression.kind else {
+        panic!("expected a binary expression");
+    };
+    assert_eq!(negation.span, Span::new(4, 12));
+    assert_eq!(call.span, Span::new(15, 24));
+}
+
+#[test]
+fn error_spans() {
+    let span_of = |input| {
+        crate::parser::Parser::<f64>::new(input)
+            .parse_program()
+            .unwrap_err()
+            .span()
+    };
+    assert_eq!(span_of("2 + * 3;"), Span::new(4, 5));
+    assert_eq!(span_of("(1 + 2"), Span::new(6, 6));
+    assert_eq!(span_of("1 + $;"), Span::new(4, 5));
+    assert_eq!(span_of("f(x, 2) = x;"), Span::new(5, 6));
+}
+
+lexer_test!(
+    comparison_binds_looser_than_arithmetic,
+    "a + 1 < b * 2 && !c;",
+    [Statement::Expression(
+        ExpressionKind::Binary(
+            Box::new(
+                ExpressionKind::Binary(
+                    Box::new(
+                        ExpressionKind::Binary(
+                            Box::new(ExpressionKind::Variable("a".to_string()).into()),
+                            Operator::Plus,
+                            Box::new(ExpressionKind::Number(1f64).into())
+                        )
+                        .into()
+                    ),
+                    Operator::Less,
+                    Box::new(
+                        ExpressionKind::Binary(
+                            Box::new(ExpressionKind::Variable("b".to_string()).into()),
+                            Operator::Star,
+                            Box::new(ExpressionKind::Number(2f64).into())
+                        )
+                        .into()
+                    )
+                )
+                .into()
+            ),
+            Operator::And,
+            Box::new(
+                ExpressionKind::Unary(
+                    UnaryOp::Not,
+                    Box::new(ExpressionKind::Variable("c".to_string()).into())
+                )
+                .into()
+            )
+        )
+        .into()
+    ),]
+);
+
+lexer_test!(
+    conditional_expression,
+    "y = if x > 0 then x else -x;",
+    [Statement::Assignment(
+        name("y"),
+        ExpressionKind::Conditional(
+            Box::new(
+                ExpressionKind::Binary(
+                    Box::new(ExpressionKind::Variable("x".to_string()).into()),
+                    Operator::Greater,
+                    Box::new(ExpressionKind::Number(0f64).into())
+                )
+                .into()
+            ),
+            Box::new(ExpressionKind::Variable("x".to_string()).into()),
+            Box::new(
+                ExpressionKind::Unary(
+                    UnaryOp::Negative,
+                    Box::new(ExpressionKind::Variable("x".to_string()).into())
+                )
+                .into()
+            )
+        )
+        .into()
+    ),]
+);
+
+#[test]
+fn conditional_requires_else() {
+    let mut parser = crate::parser::Parser::<f64>::new("if x then 1;");
+    assert!(matches!(
+        parser.parse_program(),
+        Err(crate::parser::error::ParserError::UnexpectedToken(_))
+    ));
+}
+
+#[test]
+fn final_semicolon_is_optional() {
+    let parse = |input| crate::parser::Parser::<f64>::new(input).parse_program();
+    assert_eq!(
+        parse("x = 1; x + 2").unwrap(),
+        parse("x = 1; x + 2;").unwrap(),
+        "a missing final `;` changes nothing"
+    );
+    assert_eq!(parse("1 + 2").unwrap().len(), 1);
+    assert_eq!(
+        parse("x = 1 y = 2").unwrap_err().span(),
+        Span::new(8, 9),
+        "statements still need separating"
+    );
+}
+
+fn parse(input: &str) -> Vec<Statement<f64>> {
+    crate::parser::Parser::new(input).parse_program().unwrap()
+}
+
+/// Macro to generate implicit multiplication tests, comparing against the explicit spelling
+macro_rules! implicit_test {
+    ($name:ident, $input:expr, $explicit:expr) => {
+        #[test]
+        fn $name() {
+            assert_eq!(
+                parse($input),
+                parse($explicit),
+                "Implicit multiplication mismatch in test '{}'\ninput: {}",
+                stringify!($name),
+                $input,
+            );
+        }
+    };
+}
+
+implicit_test!(implicit_constant, "2pi", "2 * pi");
+implicit_test!(implicit_parenthesized, "3(x + 1)", "3 * (x + 1)");
+implicit_test!(implicit_groups, "(a + b)(a - b)", "(a + b) * (a - b)");
+implicit_test!(implicit_call, "2 sin(x)", "2 * sin(x)");
+implicit_test!(implicit_names, "2 x y", "2 * x * y");
+implicit_test!(implicit_numbers, "2 3", "2 * 3");
+implicit_test!(implicit_before_power, "3x^2", "3 * x ^ 2");
+implicit_test!(implicit_same_as_star, "1 / 2x", "1 / 2 * x");
+implicit_test!(implicit_result_ref, "2$1", "2 * $1");
+implicit_test!(implicit_after_unary, "-2x", "(-2) * x");
+implicit_test!(calls_stay_calls, "f(x)(y)", "f(x) * y");
+implicit_test!(
+    implicit_in_definition,
+    "area(r) = pi r^2",
+    "area(r) = pi * r ^ 2"
+);
+
+/// Macro to generate postfix operator tests, comparing against the parenthesized spelling
+macro_rules! postfix_test {
+    ($name:ident, $input:expr, $parenthesized:expr) => {
+        #[test]
+        fn $name() {
+            assert_eq!(
+                parse($input),
+                parse($parenthesized),
+                "Postfix operator mismatch in test '{}'\ninput: {}",
+                stringify!($name),
+                $input,
+            );
+        }
+    };
+}
+
+postfix_test!(factorial_before_negation, "-3!", "-(3!)");
+postfix_test!(factorial_before_power, "2 ^ 3!", "2 ^ (3!)");
+postfix_test!(repeated_postfix, "3!!%", "((3!)!)%");
+postfix_test!(percent_before_sign, "50% - 5", "(50%) - 5");
+postfix_test!(percent_before_operator, "200 * 15% * 2", "200 * (15%) * 2");
+postfix_test!(percent_between_operands, "7 % 3 + 1", "(7 mod 3) + 1");
+postfix_test!(percent_before_group, "7 % (3)", "7 mod 3");
+postfix_test!(percent_before_negative, "7 % -3", "7 mod (-3)");
+postfix_test!(percent_before_positive, "7 % +3", "7 mod (+3)");
+postfix_test!(
+    percent_before_signed_group,
+    "7 % -(1 + 2)",
+    "7 mod (-(1 + 2))"
+);
+postfix_test!(percent_before_loose_sign, "50% -5", "(50%) - 5");
+postfix_test!(percent_touching_sign, "50 %-5", "(50%) - 5");
+postfix_test!(floor_division, "7 // 2 * 3", "(7 // 2) * 3");
+postfix_test!(factorial_then_implicit_product, "2 3! x", "2 * (3!) * x");
+
+#[test]
+fn postfix_operators() {
+    let factorial = |operand| ExpressionKind::Unary(UnaryOp::Factorial, Box::new(operand));
+    assert_eq!(
+        parse("n!% ;"),
+        [Statement::Expression(
+            ExpressionKind::Unary(
+                UnaryOp::Percent,
+                Box::new(factorial(ExpressionKind::Variable("n".into()).into()).into())
+            )
+            .into()
+        )]
+    );
+    let Statement::Expression(expression) = parse("2 + (x)!").remove(0) else {
+        panic!("expected an expression");
+    };
+    assert_eq!(expression.span, Span::new(0, 8));
+    let ExpressionKind::Binary(_, _, right) = expression.kind else {
+        panic!("expected a sum");
+    };
+    assert_eq!(right.span, Span::new(4, 8));
+}
+
+/// Macro to generate prefix operator tests, comparing against the parenthesized spelling
+macro_rules! prefix_test {
+    ($name:ident, $input:expr, $parenthesized:expr) => {
+        #[test]
+        fn $name() {
+            assert_eq!(
+                parse($input),
+                parse($parenthesized),
+                "Prefix operator mismatch in test '{}'\ninput: {}",
+                stringify!($name),
+                $input,
+            );
+        }
+    };
+}
+
+prefix_test!(negation_below_power, "-2^2", "-(2^2)");
+prefix_test!(negation_above_product, "-2 * 3", "(-2) * 3");
+prefix_test!(negation_before_sum, "-x + 1", "(-x) + 1");
+prefix_test!(negated_right_operand, "2 * -3^2", "2 * (-(3^2))");
+prefix_test!(negative_exponent, "2^-1", "2^(-1)");
+prefix_test!(negative_exponent_ends, "2^-1 * 3", "(2^(-1)) * 3");
+prefix_test!(negated_power_tower, "-2^-3^2", "-(2^(-(3^2)))");
+prefix_test!(repeated_prefix, "--x^2", "-(-(x^2))");
+prefix_test!(negated_factorial_power, "-3!^2", "-((3!)^2)");
+prefix_test!(not_above_power, "!x^2", "(!x)^2");
+prefix_test!(not_of_negation, "!-x^2", "!(-(x^2))");
+
+#[test]
+fn tight_negation() {
+    let options = crate::parser::ParserOptions {
+        tight_negation: true,
+        ..Default::default()
+    };
+    let tight = |input| {
+        crate::parser::Parser::<f64>::with_options(input, options)
+            .parse_program()
+            .unwrap()
+    };
+    assert_eq!(tight("-2^2"), parse("(-2)^2"));
+    assert_eq!(tight("2^-1^2"), parse("2^((-1)^2)"));
+    assert_eq!(tight("-x^2 * 3"), parse("((-x)^2) * 3"));
+    assert_eq!(tight("-3!"), parse("-(3!)"));
+    assert_eq!(tight("!x^2"), parse("(!x)^2"));
+}
+
+#[test]
+fn tight_implicit_multiplication() {
+    let options = crate::parser::ParserOptions {
+        tight_implicit_multiplication: true,
+        ..Default::default()
+    };
+    let tight = |input| {
+        crate::parser::Parser::<f64>::with_options(input, options)
+            .parse_program()
+            .unwrap()
+    };
+    assert_eq!(tight("1 / 2x"), parse("1 / (2 * x)"));
+    assert_eq!(tight("a * 2b / c"), parse("a * (2 * b) / c"));
+    assert_eq!(tight("2^3x"), parse("2 ^ 3 * x"));
+    assert_eq!(tight("2x + 1"), parse("2 * x + 1"));
+}
+
+#[test]
+fn line_break_ends_statement_before_operand() {
+    assert_eq!(parse("2\n3"), parse("2; 3"), "no product across lines");
+    assert_eq!(parse("x\n(1)"), parse("x; (1)"), "no call either");
+    assert_eq!(parse("x # note\n!y"), parse("x; !y"), "no factorial either");
+    assert_eq!(parse("x = 2 # two\ny = x * 3;"), parse("x = 2; y = x * 3;"));
+}
+
+#[test]
+fn line_break_in_parentheses_is_whitespace() {
+    assert_eq!(parse("(2\n3)"), parse("(2 * 3)"));
+    assert_eq!(parse("(2\nx)"), parse("(2 * x)"));
+    assert_eq!(parse("(x\n(1))"), parse("(x(1))"));
+    assert_eq!(parse("max(1,\n  2\n  -3)"), parse("max(1, 2 - 3)"));
+    assert_eq!(parse("(2\nx) 3\n4"), parse("(2 * x) * 3; 4"));
+}
+
+#[test]
+fn statements_after_errors_still_parse() {
+    let mut parser = crate::parser::Parser::<f64>::new("x = (1 +\n2 2; y = ;z = 3\n)\n4 # end");
+    let mut next = || {
+        parser
+            .next_statement()
+            .map(|statement| statement.map_err(|e| e.span()))
+    };
+    assert_eq!(next(), Some(Err(Span::new(12, 13))));
+    assert_eq!(next(), Some(Err(Span::new(18, 19))));
+    assert_eq!(
+        next(),
+        Some(Ok(Statement::Assignment(
+            name("z"),
+            ExpressionKind::Number(3.0).into()
+        )))
+    );
+    assert_eq!(next(), Some(Err(Span::new(25, 26))));
+    assert_eq!(
+        next(),
+        Some(Ok(Statement::Expression(
+            ExpressionKind::Number(4.0).into()
+        )))
+    );
+    assert_eq!(next(), None);
+
+    let mut parser = crate::parser::Parser::<f64>::new("1 +* 2\n- 3\n4");
+    assert_eq!(
+        parser.next_statement().unwrap().unwrap_err().span(),
+        Span::new(3, 4)
+    );
+    assert_eq!(
+        parser.next_statement().unwrap().unwrap(),
+        Statement::Expression(ExpressionKind::Number(4.0).into()),
+        "the continued line belongs to the failed statement"
+    );
+}
+
+#[test]
+fn expression_continues_after_line_break() {
+    assert_eq!(parse("x = 2 *\n3"), parse("x = 2 * 3"));
+    assert_eq!(parse("x = 5\n* 2 # twice\n^ 2"), parse("x = 5 * 2 ^ 2"));
+    assert_eq!(parse("f(x) =\n x + 1"), parse("f(x) = x + 1"));
+    assert_eq!(parse("(a\n- b)"), parse("a - b"));
+    assert_eq!(parse("max(a,\n-b)"), parse("max(a, -b)"));
+    assert_eq!(parse("total = a\n- b"), parse("total = a - b"));
+    assert_eq!(parse("x = 5\n-1"), parse("x = 5 - 1"));
+    assert_eq!(parse("1\n+ 2 # more\n* 3\n4"), parse("1 + 2 * 3; 4"));
+    assert_eq!(parse("7\n% 3"), parse("7 % 3"));
+    assert_eq!(
+        parse("x = 5;\n-1"),
+        parse("x = 5; -1"),
+        "unless `;` ends the line"
+    );
+    assert_eq!(
+        parse("if x > 0\nthen 1\nelse 2"),
+        parse("if x > 0 then 1 else 2")
+    );
+}
+
+#[test]
+fn implicit_product_spans() {
+    match parse("1 + 2x").remove(0) {
+        Statement::Expression(expression) => {
+            assert_eq!(expression.span, Span::new(0, 6));
+            let ExpressionKind::Binary(_, Operator::Plus, product) = expression.kind else {
+                panic!("expected a sum");
+            };
+            assert_eq!(product.span, Span::new(4, 6));
+        }
+        statement => panic!("not an expression: {statement:?}"),
+    }
+}
+
+/// Macro to generate printer tests, which also check that the printed text parses into the
+/// same statements again
+macro_rules! printer_test {
+    ($name:ident, $input:expr, $expected:expr) => {
+        #[test]
+        fn $name() {
+            let statements = parse($input);
+            let printed: Vec<_> = statements.iter().map(ToString::to_string).collect();
+            let printed = printed.join("; ");
+            assert_eq!(
+                printed,
+                $expected,
+                "Printed text mismatch in test '{}'\ninput: {}",
+                stringify!($name),
+                $input,
+            );
+            assert_eq!(
+                parse(&printed),
+                statements,
+                "Round trip mismatch in test '{}'\ninput: {}\nprinted: {}",
+                stringify!($name),
+                $input,
+                printed,
+            );
+        }
+    };
+}
+
+printer_test!(print_number, "2.50", "2.5");
+printer_test!(print_spacing, "1+2*x", "1 + 2 * x");
+printer_test!(print_redundant_parentheses, "((a)) + (b * c)", "a + b * c");
+printer_test!(print_needed_parentheses, "(a + b) * c", "(a + b) * c");
+printer_test!(print_left_associative, "(a - b) - c", "a - b - c");
+printer_test!(
+    print_left_associative_right_operand,
+    "a - (b - c)",
+    "a - (b - c)"
+);
+printer_test!(
+    print_mixed_priority,
+    "a / (b * c) + (d / e) * f",
+    "a / (b * c) + d / e * f"
+);
+printer_test!(print_right_associative, "a ^ (b ^ c)", "a ^ b ^ c");
+printer_test!(
+    print_right_associative_left_operand,
+    "(a ^ b) ^ c",
+    "(a ^ b) ^ c"
+);
+printer_test!(print_unary, "-x + !y", "-x + !y");
+printer_test!(print_negated_power_base, "(-x) ^ 2", "(-x) ^ 2");
+printer_test!(print_negated_power, "-(x ^ 2)", "-x ^ 2");
+printer_test!(
+    print_unary_of_binary,
+    "-(x * y) + !(x ^ 2)",
+    "-(x * y) + !(x ^ 2)"
+);
+printer_test!(print_negative_exponent, "2 ^ (-1) * 3", "2 ^ -1 * 3");
+printer_test!(print_prefix_chain, "(!(-x)) ^ 2", "(!-x) ^ 2");
+printer_test!(print_nested_unary, "-(-(+x))", "--+x");
+printer_test!(
+    print_logical,
+    "!(a && b) || c == (d < e)",
+    "!(a && b) || c == d < e"
+);
+printer_test!(
+    print_comparisons,
+    "(a == b) < (c != d)",
+    "(a == b) < (c != d)"
+);
+printer_test!(print_postfix, "-(3!) + (x!)%", "-3! + x!%");
+printer_test!(
+    print_postfix_of_unary,
+    "(-3)! * (a + b)%",
+    "(-3)! * (a + b)%"
+);
+printer_test!(print_remainder, "7 % 3 // (2 % 1)", "7 mod 3 // (2 mod 1)");
+printer_test!(print_call, "max( x , (y + 1) ,2)", "max(x, y + 1, 2)");
+printer_test!(print_empty_call, "f()", "f()");
+printer_test!(print_result_refs, "$1 + ans", "$1 + ans");
+printer_test!(
+    print_conditional,
+    "if (a < b) then (a) else b + 1",
+    "if a < b then a else b + 1"
+);
+printer_test!(
+    print_conditional_operand,
+    "(if a then b else c) + 1",
+    "(if a then b else c) + 1"
+);
+printer_test!(
+    print_conditional_in_unary,
+    "-(if a then b else c)",
+    "-(if a then b else c)"
+);
+printer_test!(print_assignment, "x = (1 + 2)", "x = 1 + 2");
+printer_test!(print_function_definition, "f(x,y)=x^(y)", "f(x, y) = x ^ y");
+printer_test!(print_program, "a = 1;; f(a) * 2;", "a = 1; ; f(a) * 2");
+
+#[test]
+fn print_computed_numbers() {
+    let number = |value: f64| Box::new(Expression::from(ExpressionKind::Number(value)));
+    let variable = || Box::new(Expression::from(ExpressionKind::Variable("x".to_string())));
+    let power = Expression::from(ExpressionKind::Binary(
+        number(-2.0),
+        Operator::Caret,
+        variable(),
+    ));
+    assert_eq!(power.to_string(), "(-2) ^ x");
+    let product = Expression::from(ExpressionKind::Binary(
+        variable(),
+        Operator::Star,
+        number(-0.5),
+    ));
+    assert_eq!(product.to_string(), "x * -0.5");
+    let complex = |re: f64, im: f64| {
+        Box::new(Expression::from(ExpressionKind::Number(
+            num_complex::Complex64::new(re, im),
+        )))
+    };
+    let x = Box::new(Expression::from(ExpressionKind::Variable("x".to_string())));
+    let scaled = Expression::from(ExpressionKind::Binary(x, Operator::Star, complex(1.0, 2.0)));
+    assert_eq!(scaled.to_string(), "x * (1 + 2i)");
+    let rotated = Expression::from(ExpressionKind::Unary(UnaryOp::Negative, complex(0.0, 2.0)));
+    assert_eq!(rotated.to_string(), "-2i");
+}
@@ -0,0 +1,427 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use num_bigfloat::BigFloat;
+use num_complex::Complex64;
+use rust_calc_lib::evaluator::LAST_RESULT;
+use rust_calc_lib::format::FormatOptions;
+use rust_calc_lib::lexer::Lexer;
+use rust_calc_lib::lexer::token::{Literal, Punctuation, Token};
+use rust_calc_lib::numeric::integer::Integer;
+use rust_calc_lib::numeric::rational::Rational;
+use rust_calc_lib::numeric::{Arity, NumericValue};
+use rust_calc_lib::span::Span;
+use rustyline::completion::{Completer, Pair};
+use rustyline::highlight::{CmdKind, Highlighter};
+use rustyline::hint::Hinter;
+use rustyline::validate::{ValidationContext, ValidationResult, Validator};
+use rustyline::{Context, Helper};
+
+use crate::mode::{Mode, Session};
+
+const RESET: &str = "\x1b[0m";
+const NUMBER: &str = "\x1b[33m";
+const VALUE: &str = "\x1b[36m";
+const FUNCTION: &str = "\x1b[34m";
+const KEYWORD: &str = "\x1b[35m";
+const ERROR: &str = "\x1b[4;31m";
+const HINT: &str = "\x1b[2m";
+
+/// A token of the input line, stripped of its numeric value so it does not depend on the mode.
+#[derive(Debug, PartialEq)]
+enum Lexeme {
+    Number,
+    Identifier(String),
+    Keyword,
+    /// A `$n` reference to an earlier result.
+    ResultRef,
+    Symbol,
+}
+
+/// Splits `line` into lexemes as the current mode sees them. A lexer error or a number the mode
+/// cannot represent yields its span without a lexeme, and lexing resumes right after a lexer
+/// error so the rest of the line is still coloured.
+fn lex<N: NumericValue>(line: &str) -> Vec<(Span, Option<Lexeme>)> {
+    let mut lexemes = Vec::new();
+    let mut offset = 0;
+    loop {
+        let mut resume = None;
+        for token in Lexer::new(&line[offset..]) {
+            let (span, lexeme) = match token {
+                Ok(token) => {
+                    let lexeme = match token.node {
+                        Token::Number(literal) => {
+                            representable::<N>(&literal).then_some(Lexeme::Number)
+                        }
+                        Token::Identifier(name) => Some(Lexeme::Identifier(name)),
+                        Token::Keyword(_) => Some(Lexeme::Keyword),
+                        Token::ResultRef(_) => Some(Lexeme::ResultRef),
+                        _ => Some(Lexeme::Symbol),
+                    };
+                    (token.span, lexeme)
+                }
+                Err(e) => {
+                    resume = Some(e.span().end + offset);
+                    (e.span(), None)
+                }
+            };
+            let span = Span::new(span.start + offset, span.end + offset);
+            lexemes.push((span, lexeme));
+        }
+        match resume {
+            Some(end) => offset = end,
+            None => return lexemes,
+        }
+    }
+}
+
+/// Whether the number literal has a value in the mode with values `N`.
+fn representable<N: NumericValue>(literal: &Literal) -> bool {
+    N::from_literal(literal).is_ok() && (!literal.imaginary || N::imaginary_unit().is_some())
+}
+
+/// Placeholder parameter names for a builtin taking `arity` arguments, e.g. `x, y`.
+fn placeholders(arity: Arity) -> String {
+    let count = arity.max.unwrap_or(arity.min).max(arity.min);
+    let name = |i: usize| match (count, i) {
+        (..=3, _) => ["x", "y", "z"][i].to_owned(),
+        _ => format!("x{}", i + 1),
+    };
+    let mut parameters: Vec<_> = (0..arity.min).map(name).collect();
+    match arity.max {
+        None => parameters.push(String::from("...")),
+        Some(max) => parameters.extend((arity.min..max).map(|i| format!("[{}]", name(i)))),
+    }
+    parameters.join(", ")
+}
+
+/// Completion, highlighting and hints for the REPL, using the names known to the session.
+#[derive(Default)]
+pub struct CalcHelper {
+    mode: Option<Mode>,
+    /// Names of the variables and constants, and `ans`.
+    values: Vec<String>,
+    /// The parameters of every builtin and user function, by function name.
+    functions: HashMap<String, String>,
+}
+
+impl CalcHelper {
+    /// Picks up the names defined in `session` since the last prompt.
+    pub fn update(&mut self, session: &dyn Session, mode: Mode) {
+        let format = FormatOptions::default();
+        self.mode = Some(mode);
+        self.values = session
+            .variables(&format)
+            .into_iter()
+            .chain(session.constants(&format))
+            .map(|(name, _)| name)
+            .chain([LAST_RESULT.to_owned()])
+            .collect();
+        self.functions = session
+            .builtins()
+            .into_iter()
+            .map(|(name, arity)| (name.to_owned(), placeholders(arity)))
+            .chain(
+                session
+                    .user_functions()
+                    .into_iter()
+                    .map(|(name, parameters)| (name, parameters.join(", "))),
+            )
+            .collect();
+    }
+
+    fn lex(&self, line: &str) -> Vec<(Span, Option<Lexeme>)> {
+        match self.mode {
+            Some(Mode::Float) => lex::<f64>(line),
+            Some(Mode::BigFloat) | None => lex::<BigFloat>(line),
+            Some(Mode::Rational) => lex::<Rational>(line),
+            Some(Mode::Int) => lex::<Integer>(line),
+            Some(Mode::Complex) => lex::<Complex64>(line),
+        }
+    }
+}
+
+impl Completer for CalcHelper {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let start = line[..pos]
+            .rfind(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .map_or(0, |i| i + 1);
+        let prefix = &line[start..pos];
+        if prefix.is_empty() || prefix.starts_with(|c: char| c.is_ascii_digit()) {
+            return Ok((pos, Vec::new()));
+        }
+        let values = self.values.iter().map(|name| Pair {
+            display: name.clone(),
+            replacement: name.clone(),
+        });
+        let functions = self.functions.iter().map(|(name, parameters)| Pair {
+            display: format!("{name}({parameters})"),
+            replacement: format!("{name}("),
+        });
+        let mut candidates: Vec<_> = values
+            .chain(functions)
+            .filter(|pair| pair.replacement.starts_with(prefix))
+            .collect();
+        candidates.sort_by(|a, b| a.display.cmp(&b.display));
+        Ok((start, candidates))
+    }
+}
+
+impl Hinter for CalcHelper {
+    type Hint = String;
+
+    /// Shows the parameters of a function right after its opening parenthesis is typed.
+    fn hint(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> Option<String> {
+        if pos != line.len() {
+            return None;
+        }
+        let call = line.strip_suffix('(')?;
+        let start = call
+            .rfind(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .map_or(0, |i| i + 1);
+        let parameters = self.functions.get(&call[start..])?;
+        Some(format!("{parameters})"))
+    }
+}
+
+impl Highlighter for CalcHelper {
+    fn highlight<'l>(&self, line: &'l str, _pos: usize) -> Cow<'l, str> {
+        if line.trim_start().starts_with(':') {
+            return Cow::Borrowed(line);
+        }
+        let mut highlighted = String::with_capacity(line.len() * 2);
+        let mut last = 0;
+        for (span, lexeme) in self.lex(line) {
+            let color = match lexeme {
+                None => ERROR,
+                Some(Lexeme::Number) => NUMBER,
+                Some(Lexeme::Keyword) => KEYWORD,
+                Some(Lexeme::ResultRef) => VALUE,
+                Some(Lexeme::Identifier(name)) if self.functions.contains_key(&name) => FUNCTION,
+                Some(Lexeme::Identifier(name)) if self.values.contains(&name) => VALUE,
+                Some(Lexeme::Identifier(_) | Lexeme::Symbol) => continue,
+            };
+            highlighted.push_str(&line[last..span.start]);
+            highlighted.push_str(color);
+            highlighted.push_str(&line[span.start..span.end]);
+            highlighted.push_str(RESET);
+            last = span.end;
+        }
+        highlighted.push_str(&line[last..]);
+        Cow::Owned(highlighted)
+    }
+
+    fn highlight_hint<'h>(&self, hint: &'h str) -> Cow<'h, str> {
+        Cow::Owned(format!("{HINT}{hint}{RESET}"))
+    }
+
+    fn highlight_char(&self, _line: &str, _pos: usize, _kind: CmdKind) -> bool {
+        true
+    }
+}
+
+/// Whether `input` needs another line to be complete: a parenthesis outside a comment is left
+/// open, or the input ends in a binary operator or `=`. Input that does not lex is complete, so
+/// the error is reported rather than waiting for more.
+fn incomplete(input: &str) -> bool {
+    let code = input
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or(line));
+    let depth = code.flat_map(str::chars).fold(0i32, |depth, c| match c {
+        '(' => depth + 1,
+        ')' => depth - 1,
+        _ => depth,
+    });
+    let last = Lexer::new(input).collect::<Result<Vec<_>, _>>().ok();
+    let trailing = match last.as_ref().and_then(|tokens| tokens.last()) {
+        Some(token) => match &token.node {
+            Token::Operator(operator) => operator.is_binary(),
+            Token::Punctuation(Punctuation::Assignment) => true,
+            _ => false,
+        },
+        None => false,
+    };
+    depth > 0 || trailing
+}
+
+impl Validator for CalcHelper {
+    /// Keeps reading lines while the input is [`incomplete`], so long expressions can be split.
+    fn validate(&self, ctx: &mut ValidationContext<'_>) -> rustyline::Result<ValidationResult> {
+        if incomplete(ctx.input()) {
+            Ok(ValidationResult::Incomplete)
+        } else {
+            Ok(ValidationResult::Valid(None))
+        }
+    }
+}
+
+impl Helper for CalcHelper {}
+
+#[cfg(test)]
+mod tests {
+    use num_complex::Complex64;
+    use rust_calc_lib::format::FormatOptions;
+    use rust_calc_lib::numeric::Arity;
+    use rust_calc_lib::numeric::integer::Integer;
+    use rust_calc_lib::span::Span;
+    use rustyline::Context;
+    use rustyline::completion::Completer;
+    use rustyline::hint::Hinter;
+    use rustyline::history::DefaultHistory;
+
+    use super::{CalcHelper, Lexeme, incomplete, lex, placeholders};
+    use crate::mode::Mode;
+
+    /// A helper that knows the names defined by running `input` in a fresh float session.
+    fn helper(input: &str) -> CalcHelper {
+        let mut session = Mode::Float.session();
+        assert!(session.run_script(input, None, &FormatOptions::default(), false));
+        let mut helper = CalcHelper::default();
+        CalcHelper::update(&mut helper, session.as_ref(), Mode::Float);
+        helper
+    }
+
+    fn complete(helper: &CalcHelper, line: &str) -> (usize, Vec<(String, String)>) {
+        let history = DefaultHistory::new();
+        let (start, pairs) = helper
+            .complete(line, line.len(), &Context::new(&history))
+            .unwrap();
+        let pairs = pairs
+            .into_iter()
+            .map(|pair| (pair.display, pair.replacement))
+            .collect();
+        (start, pairs)
+    }
+
+    fn hint(helper: &CalcHelper, line: &str, pos: usize) -> Option<String> {
+        let history = DefaultHistory::new();
+        helper.hint(line, pos, &Context::new(&history))
+    }
+
+    fn pair(display: &str, replacement: &str) -> (String, String) {
+        (display.to_owned(), replacement.to_owned())
+    }
+
+    #[test]
+    fn lexemes_carry_their_spans() {
+        assert_eq!(
+            lex::<f64>("2 * rate if"),
+            [
+                (Span::new(0, 1), Some(Lexeme::Number)),
+                (Span::new(2, 3), Some(Lexeme::Symbol)),
+                (Span::new(4, 8), Some(Lexeme::Identifier("rate".into()))),
+                (Span::new(9, 11), Some(Lexeme::Keyword)),
+            ]
+        );
+        assert_eq!(
+            lex::<f64>("$2"),
+            [(Span::new(0, 2), Some(Lexeme::ResultRef))]
+        );
+    }
+
+    #[test]
+    fn lexing_resumes_after_an_error() {
+        assert_eq!(
+            lex::<f64>("1 @ x @@ 2"),
+            [
+                (Span::new(0, 1), Some(Lexeme::Number)),
+                (Span::new(2, 3), None),
+                (Span::new(4, 5), Some(Lexeme::Identifier("x".into()))),
+                (Span::new(6, 7), None),
+                (Span::new(7, 8), None),
+                (Span::new(9, 10), Some(Lexeme::Number)),
+            ]
+        );
+    }
+
+    #[test]
+    fn numbers_the_mode_cannot_represent_are_errors() {
+        assert_eq!(lex::<Integer>("1.5"), [(Span::new(0, 3), None)]);
+        assert_eq!(lex::<f64>("2i"), [(Span::new(0, 2), None)]);
+        assert_eq!(
+            lex::<Complex64>("2i"),
+            [(Span::new(0, 2), Some(Lexeme::Number))]
+        );
+    }
+
+    #[test]
+    fn placeholders_follow_the_arity() {
+        assert_eq!(placeholders(Arity::exact(0)), "");
+        assert_eq!(placeholders(Arity::exact(1)), "x");
+        assert_eq!(placeholders(Arity::exact(3)), "x, y, z");
+        assert_eq!(placeholders(Arity::exact(4)), "x1, x2, x3, x4");
+        assert_eq!(
+            placeholders(Arity {
+                min: 1,
+                max: Some(2)
+            }),
+            "x, [y]"
+        );
+        assert_eq!(placeholders(Arity::at_least(1)), "x, ...");
+        assert_eq!(placeholders(Arity::at_least(0)), "...");
+    }
+
+    #[test]
+    fn completes_values_and_functions() {
+        let helper = helper("rate = 2; rated(a, b) = a * b");
+        assert_eq!(
+            complete(&helper, "1 + rat"),
+            (4, vec![pair("rate", "rate"), pair("rated(a, b)", "rated(")])
+        );
+        assert_eq!(complete(&helper, "sq"), (0, vec![pair("sqrt(x)", "sqrt(")]));
+        assert_eq!(complete(&helper, "an"), (0, vec![pair("ans", "ans")]));
+    }
+
+    #[test]
+    fn does_not_complete_numbers_or_nothing() {
+        let helper = helper("");
+        assert_eq!(complete(&helper, "1 + "), (4, Vec::new()));
+        assert_eq!(complete(&helper, "2e"), (2, Vec::new()));
+    }
+
+    #[test]
+    fn hints_parameters_after_an_opening_parenthesis() {
+        let helper = helper("rated(a, b) = a * b; rate = 2");
+        assert_eq!(hint(&helper, "1 + rated(", 10), Some("a, b)".into()));
+        assert_eq!(hint(&helper, "sqrt(", 5), Some("x)".into()));
+        assert_eq!(hint(&helper, "rate(", 5), None);
+        assert_eq!(hint(&helper, "sqrt(", 4), None);
+        assert_eq!(hint(&helper, "sqrt", 4), None);
+    }
+
+    #[test]
+    fn open_parentheses_keep_reading() {
+        assert!(incomplete("(1 +"));
+        assert!(incomplete("max(1,\n  (2"));
+        assert!(incomplete("f(x) = (x # close it later )"));
+        assert!(!incomplete("max(1,\n  2)"));
+        assert!(!incomplete("1 # (comment"));
+    }
+
+    #[test]
+    fn trailing_operators_keep_reading() {
+        assert!(incomplete("1 +"));
+        assert!(incomplete("total = a *\nb -"));
+        assert!(incomplete("x ="));
+        assert!(incomplete("2 mod # more below"));
+        assert!(!incomplete("1 + 2"));
+        assert!(!incomplete("5!"));
+        assert!(!incomplete("15%"));
+        assert!(!incomplete(""));
+    }
+
+    #[test]
+    fn invalid_input_is_handed_to_the_parser() {
+        assert!(!incomplete("1 + 2)"));
+        assert!(!incomplete(")("));
+        assert!(!incomplete("1 @ +"));
+        assert!(!incomplete("(1 +) *)"));
+    }
+}
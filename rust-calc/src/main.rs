@@ -1,100 +1,258 @@
-use std::collections::HashMap;
-use std::env;
-use std::fmt::Debug;
+mod helper;
+mod mode;
+mod persist;
+mod repl;
 
-use num_bigfloat::BigFloat;
-use rust_calc_lib::evaluator::Evaluator;
-use rust_calc_lib::numeric::{BuiltinFn, NumericValue};
-use rustyline::DefaultEditor;
+use std::error::Error;
+use std::io::{self, IsTerminal, Read};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::{env, fs};
 
-pub trait PrettyDisplay {
-    fn pretty(&self) -> String;
-}
+use rust_calc_lib::export::ExportFormat;
+use rust_calc_lib::format::{FormatOptions, Notation};
+use rust_calc_lib::parser::ParserOptions;
+use rust_calc_lib::span::Span;
+
+use crate::mode::Mode;
+use crate::repl::Repl;
 
-impl PrettyDisplay for BigFloat {
-    fn pretty(&self) -> String {
-        self.to_f64().to_string()
+/// Prints `error` and its sources, followed by the offending input line with a caret under
+/// `span`, the part it refers to. `origin` names the file the input was read from, if any, and is
+/// reported together with the line and column of the error.
+fn report_error(origin: Option<&str>, input: &str, error: &dyn Error, span: Option<Span>) {
+    let mut message = error.to_string();
+    let mut source = error.source();
+    while let Some(cause) = source {
+        message.push_str(&format!(": {cause}"));
+        source = cause.source();
     }
+    eprintln!("Error: {message}");
+    let Some(span) = span else {
+        return;
+    };
+    let line_start = input[..span.start].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = input[span.start..]
+        .find('\n')
+        .map_or(input.len(), |i| span.start + i);
+    let line = &input[line_start..line_end];
+    let caret_span = Span::new(span.start, span.end.min(line_end));
+    let padding = input[line_start..caret_span.start].chars().count();
+    if let Some(origin) = origin {
+        let line_number = input[..line_start].matches('\n').count() + 1;
+        eprintln!(" --> {origin}:{line_number}:{}", padding + 1);
+    }
+    let width = input[caret_span.start..caret_span.end]
+        .chars()
+        .count()
+        .max(1);
+    eprintln!("  {line}");
+    eprintln!("  {}{}", " ".repeat(padding), "^".repeat(width));
 }
 
-struct DefaultBuiltins;
-
-impl BuiltinFn<BigFloat> for DefaultBuiltins {
-    fn call(&self, name: &str, arg: BigFloat) -> Option<BigFloat> {
-        match name {
-            "sqrt" => Some(arg.sqrt()),
-            "sin" => Some(arg.sin()),
-            "cos" => Some(arg.cos()),
-            "tan" => Some(arg.tan()),
-            "exp" => Some(arg.exp()),
-            "ln" => Some(arg.ln()),
-            "deg2rad" => {
-                let pi = num_bigfloat::PI;
-                Some(arg * pi / BigFloat::from_f64(180.0))
-            }
-            _ => None,
+/// Returns the program to run without the REPL, together with the name of the file it came
+/// from: the script given with `--file`, the arguments themselves, or else piped standard input.
+/// Arguments are never read as a file name, so `rust-calc pi` prints pi even next to a file
+/// named `pi`. Statements in a script end at a `;` or a line break, except that a line starting
+/// with an operator such as `-` continues the one before, see [`Parser::parse_program`].
+///
+/// [`Parser::parse_program`]: rust_calc_lib::parser::Parser::parse_program
+fn batch_input(file: Option<&Path>, args: &[String]) -> Result<(Option<String>, String), String> {
+    if let Some(path) = file {
+        if !args.is_empty() {
+            return Err(String::from(
+                "`--file` cannot be combined with an expression",
+            ));
         }
+        let name = path.display().to_string();
+        let script = fs::read_to_string(path).map_err(|e| format!("cannot read {name}: {e}"))?;
+        return Ok((Some(name), script));
     }
+    if args.is_empty() {
+        let mut input = String::new();
+        io::stdin()
+            .read_to_string(&mut input)
+            .map_err(|e| format!("cannot read standard input: {e}"))?;
+        return Ok((Some(String::from("<stdin>")), input));
+    }
+    Ok((None, args.join(" ")))
+}
 
-    fn constants(&self) -> HashMap<String, BigFloat> {
-        let mut constants = HashMap::new();
-
-        constants.insert(String::from("pi"), num_bigfloat::PI);
-        constants.insert(String::from("e"), num_bigfloat::E);
+/// Settings taken from the leading `--` options and `-f` on the command line.
+struct Options {
+    /// The script given with `--file` or `-f` to run instead of the arguments.
+    file: Option<PathBuf>,
+    mode: Mode,
+    format: FormatOptions,
+    /// The file of the session given with `--session`.
+    session: Option<PathBuf>,
+    /// How many results are kept for `$n` references, if set with `--history-limit`.
+    history_limit: Option<usize>,
+    /// The format given with `--format` to print formulas in instead of evaluating them.
+    export: Option<ExportFormat>,
+    /// How the input is read, `--tight-implicit` making products like `2x` bind tighter and
+    /// `--tight-negation` making `-2^2` square `-2`, as spreadsheets do.
+    parser: ParserOptions,
+}
 
-        constants
+/// Removes the leading `--` options and `-f` from `args` and returns the settings they select.
+fn parse_options(args: &mut Vec<String>) -> Result<Options, String> {
+    let mut options = Options {
+        file: None,
+        mode: Mode::BigFloat,
+        format: FormatOptions::default(),
+        session: None,
+        history_limit: None,
+        export: None,
+        parser: ParserOptions::default(),
+    };
+    while let Some(option) = args
+        .first()
+        .filter(|arg| arg.starts_with("--") || *arg == "-f")
+        .cloned()
+    {
+        args.remove(0);
+        let mut value = || {
+            (!args.is_empty())
+                .then(|| args.remove(0))
+                .ok_or_else(|| format!("`{option}` expects a value"))
+        };
+        match option.as_str() {
+            "--file" | "-f" => options.file = Some(PathBuf::from(value()?)),
+            "--mode" => options.mode = value()?.parse()?,
+            "--precision" => {
+                let precision = value()?;
+                let precision = precision
+                    .parse()
+                    .ok()
+                    .filter(|precision| *precision > 0)
+                    .ok_or_else(|| format!("invalid precision `{precision}`"))?;
+                options.format.precision = Some(precision);
+            }
+            "--notation" => options.format.notation = value()?.parse::<Notation>()?,
+            "--thousands" => options.format.thousands_separator = Some(','),
+            "--keep-zeros" => options.format.trim_zeros = false,
+            "--session" => options.session = Some(persist::session_path(&value()?)?),
+            "--format" => options.export = Some(value()?.parse()?),
+            "--tight-implicit" => options.parser.tight_implicit_multiplication = true,
+            "--tight-negation" => options.parser.tight_negation = true,
+            "--history-limit" => {
+                let limit = value()?;
+                let limit = limit
+                    .parse()
+                    .map_err(|_| format!("invalid history limit `{limit}`"))?;
+                options.history_limit = Some(limit);
+            }
+            _ => return Err(format!("unknown option `{option}`")),
+        }
     }
+    Ok(options)
 }
 
-fn repl<N: NumericValue + Debug + PrettyDisplay, F: BuiltinFn<N>>(evaluator: &mut Evaluator<N, F>) {
-    println!("RustCalc REPL (type 'exit' to quit)");
-    let mut rl = match DefaultEditor::new() {
-        Ok(res) => res,
-        Err(_) => {
-            eprintln!("Error creating a read-line default editor");
-            return;
+fn main() {
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    let options = match parse_options(&mut args) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            process::exit(2);
         }
     };
 
-    loop {
-        match rl.readline(">>>") {
-            Ok(input) => {
-                if input.trim().is_empty() {
-                    continue;
-                }
-                if input.trim() == "exit" {
-                    break;
-                }
-                match rl.add_history_entry(&input) {
-                    Ok(_) => {}
-                    Err(_) => {
-                        eprintln!("Error adding the history entry");
-                        break;
-                    }
-                };
-
-                match evaluator.parse(&input) {
-                    Ok(Some(result)) => println!("{}", result.pretty()),
-                    Ok(None) => {}
-                    Err(e) => eprintln!("Error: {:?}", e),
-                }
+    let mut session = options.mode.session();
+    if let Some(limit) = options.history_limit {
+        session.set_history_limit(limit);
+    }
+    session.set_parser_options(options.parser);
+    // a session file that did not load completely is never overwritten, it would lose what failed
+    let mut session_file = options.session;
+    if let Some(path) = session_file.as_deref().filter(|path| path.exists()) {
+        match persist::load(session.as_mut(), path, &options.format, false) {
+            Ok(true) => {}
+            Ok(false) => {
+                eprintln!(
+                    "Error: {} did not load completely and will not be saved",
+                    path.display()
+                );
+                session_file = None;
+            }
+            Err(e) => {
+                eprintln!("Error: cannot load {}: {e}", path.display());
+                process::exit(2);
             }
-            Err(_) => eprintln!("Error: failure reading a line from the repl"),
         }
     }
-}
 
-fn main() {
-    let args: Vec<String> = env::args().skip(1).collect();
-    let mut evaluator = Evaluator::new(DefaultBuiltins);
-    if args.is_empty() {
-        repl(&mut evaluator);
-    } else {
-        let input = args.join(" ");
-        match evaluator.parse(&input) {
-            Ok(Some(result)) => println!("{}", result),
-            Ok(None) => {}
-            Err(e) => eprintln!("Error: {:?}", e),
+    if args.is_empty()
+        && options.file.is_none()
+        && io::stdin().is_terminal()
+        && options.export.is_none()
+    {
+        Repl::new(session, options.mode, options.format, session_file).run();
+        return;
+    }
+
+    let succeeded = match batch_input(options.file.as_deref(), &args) {
+        Ok((origin, input)) => match options.export {
+            Some(export) => session.export(&input, origin.as_deref(), export),
+            None => session.evaluate(&input, origin.as_deref(), &options.format, false),
+        },
+        Err(e) => {
+            eprintln!("Error: {e}");
+            false
         }
+    };
+    if let Some(path) = &session_file
+        && let Err(e) = persist::save(session.as_ref(), path)
+    {
+        eprintln!("Error: cannot save to {}: {e}", path.display());
+    }
+    if !succeeded {
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use std::{env, fs, process};
+
+    use super::{batch_input, parse_options};
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn arguments_are_never_file_names() {
+        let dir = env::temp_dir().join(format!("rust-calc-{}-batch", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("pi");
+        fs::write(&path, "1 + 1").unwrap();
+        let name = path.to_str().unwrap();
+
+        let by_argument = batch_input(None, &args(&[name]));
+        let mut flagged = args(&["-f", name]);
+        let options = parse_options(&mut flagged).unwrap();
+        let by_flag = batch_input(options.file.as_deref(), &flagged);
+        let combined = batch_input(Some(&path), &args(&["2"]));
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(by_argument, Ok((None, name.to_owned())));
+        assert_eq!(by_flag, Ok((Some(name.to_owned()), String::from("1 + 1"))));
+        assert!(combined.is_err());
+        assert!(batch_input(Some(Path::new(&dir)), &[]).is_err());
+    }
+
+    #[test]
+    fn file_option_takes_a_path() {
+        let mut rest = args(&["--file", "script.calc", "--mode", "int"]);
+        let options = parse_options(&mut rest).unwrap();
+        assert_eq!(options.file.as_deref(), Some(Path::new("script.calc")));
+        assert!(rest.is_empty());
+        assert!(parse_options(&mut args(&["-f"])).is_err());
+        let mut expression = args(&["-5", "+", "f"]);
+        assert!(parse_options(&mut expression).unwrap().file.is_none());
+        assert_eq!(expression.len(), 3);
     }
 }
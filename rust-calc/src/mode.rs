@@ -0,0 +1,452 @@
+use std::collections::HashMap;
+use std::fmt::{self, Debug, Display};
+use std::str::FromStr;
+
+use rust_calc_lib::derivative;
+use rust_calc_lib::evaluator::Evaluator;
+use rust_calc_lib::export::ExportFormat;
+use rust_calc_lib::format::{FormatOptions, Notation, PrettyDisplay};
+use rust_calc_lib::numeric::bigfloat::BigFloatBuiltins;
+use rust_calc_lib::numeric::complex::ComplexBuiltins;
+use rust_calc_lib::numeric::float::FloatBuiltins;
+use rust_calc_lib::numeric::integer::IntegerBuiltins;
+use rust_calc_lib::numeric::rational::RationalBuiltins;
+use rust_calc_lib::numeric::{Arity, BuiltinFn, NumericValue, PortableValue};
+use rust_calc_lib::parser::ast::Statement;
+use rust_calc_lib::parser::{Parser, ParserOptions};
+
+use crate::report_error;
+
+/// The numeric backend calculations run on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Float,
+    BigFloat,
+    Rational,
+    Int,
+    Complex,
+}
+
+impl Mode {
+    pub const ALL: [Mode; 5] = [
+        Mode::Float,
+        Mode::BigFloat,
+        Mode::Rational,
+        Mode::Int,
+        Mode::Complex,
+    ];
+
+    /// Creates a session in this mode with the settings and variables of `session`, returning it
+    /// together with the variables that changed on the way. Results and user functions are not
+    /// carried over.
+    pub fn convert(self, session: &dyn Session) -> (Box<dyn Session>, Import) {
+        let mut converted = self.session();
+        converted.set_history_limit(session.history_limit());
+        converted.set_parser_options(session.parser_options());
+        let import = converted.import_variables(session.export_variables());
+        (converted, import)
+    }
+
+    /// Creates an empty session evaluating in this mode.
+    pub fn session(self) -> Box<dyn Session> {
+        match self {
+            Mode::Float => Box::new(Evaluator::new(FloatBuiltins)),
+            Mode::BigFloat => Box::new(Evaluator::new(BigFloatBuiltins)),
+            Mode::Rational => Box::new(Evaluator::new(RationalBuiltins)),
+            Mode::Int => Box::new(Evaluator::new(IntegerBuiltins)),
+            Mode::Complex => Box::new(Evaluator::new(ComplexBuiltins)),
+        }
+    }
+}
+
+impl Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Mode::Float => "float",
+            Mode::BigFloat => "bigfloat",
+            Mode::Rational => "rational",
+            Mode::Int => "int",
+            Mode::Complex => "complex",
+        })
+    }
+}
+
+impl FromStr for Mode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        Mode::ALL
+            .into_iter()
+            .find(|mode| mode.to_string() == s)
+            .ok_or_else(|| {
+                let modes: Vec<_> = Mode::ALL.iter().map(Mode::to_string).collect();
+                format!("unknown mode `{s}`, expected one of {}", modes.join(", "))
+            })
+    }
+}
+
+/// The variables [`Session::import_variables`] could not carry over unchanged, sorted by name.
+#[derive(Debug, Default, PartialEq)]
+pub struct Import {
+    /// Variables without a representation in the new mode, which are left out.
+    pub dropped: Vec<String>,
+    /// Variables whose value was rounded to what the new mode can represent, like `1/3` moving
+    /// to a float mode.
+    pub rounded: Vec<String>,
+}
+
+/// An [`Evaluator`] with its numeric backend erased, so that the mode can change at runtime.
+pub trait Session {
+    /// Evaluates `input`, printing the result of every expression statement and the error if
+    /// one occurs. Returns whether evaluation succeeded. `origin` names the file `input` was
+    /// read from, for error locations, and `numbered` prints results as `[n] = value` so they
+    /// can be referred to as `$n`.
+    fn evaluate(
+        &mut self,
+        input: &str,
+        origin: Option<&str>,
+        format: &FormatOptions,
+        numbered: bool,
+    ) -> bool;
+
+    /// Evaluates `input` like [`Self::evaluate`], but goes on with the next statement after
+    /// reporting an error. Returns whether every statement succeeded.
+    fn run_script(
+        &mut self,
+        input: &str,
+        origin: Option<&str>,
+        format: &FormatOptions,
+        numbered: bool,
+    ) -> bool;
+
+    /// Prints every statement of `input` as a formula in `export` instead of evaluating it, or
+    /// the error if `input` cannot be parsed. Returns whether it could be parsed. `origin` names
+    /// the file `input` was read from, for error locations.
+    fn export(&self, input: &str, origin: Option<&str>, export: ExportFormat) -> bool;
+
+    /// Prints the simplified derivative of the expression `input` with respect to `variable`, or
+    /// the error if it cannot be parsed or differentiated.
+    fn differentiate(&self, input: &str, variable: &str);
+
+    /// How many results are kept for `$n` references.
+    fn history_limit(&self) -> usize;
+
+    /// Sets how many results are kept for `$n` references.
+    fn set_history_limit(&mut self, limit: usize);
+
+    /// How the input is read.
+    fn parser_options(&self) -> ParserOptions;
+
+    fn set_parser_options(&mut self, options: ParserOptions);
+
+    /// Whether any expression result is kept for `$n` references.
+    fn has_results(&self) -> bool;
+
+    /// The variables of the session, converted for moving them to another mode.
+    fn export_variables(&self) -> Vec<(String, PortableValue)>;
+
+    /// Assigns the given variables as closely as this mode can represent them, returning those
+    /// that could not be carried over unchanged.
+    fn import_variables(&mut self, variables: Vec<(String, PortableValue)>) -> Import;
+
+    /// The variables and their formatted values, sorted by name.
+    fn variables(&self, format: &FormatOptions) -> Vec<(String, String)>;
+
+    /// The constants and their formatted values, sorted by name.
+    fn constants(&self, format: &FormatOptions) -> Vec<(String, String)>;
+
+    /// The builtin functions and how many arguments they take, sorted by name.
+    fn builtins(&self) -> Vec<(&'static str, Arity)>;
+
+    /// The user defined functions and their parameters, sorted by name.
+    fn user_functions(&self) -> Vec<(String, Vec<String>)>;
+
+    /// Removes the variable `name`, returning whether it existed.
+    fn remove_variable(&mut self, name: &str) -> bool;
+
+    /// Forgets all variables and user functions.
+    fn reset(&mut self);
+
+    /// The variables and user functions as statements, one per line, that recreate them when
+    /// evaluated. Values that cannot be written as a literal, like NaN, are left out.
+    fn script(&self) -> String;
+}
+
+/// Formats `values` and sorts them by name.
+fn sorted_values<N: PrettyDisplay>(
+    values: &HashMap<String, N>,
+    format: &FormatOptions,
+) -> Vec<(String, String)> {
+    let mut values: Vec<_> = values
+        .iter()
+        .map(|(name, value)| (name.clone(), value.pretty(format)))
+        .collect();
+    values.sort();
+    values
+}
+
+impl<N, F> Session for Evaluator<N, F>
+where
+    N: NumericValue + Debug + Display + PrettyDisplay + 'static,
+    F: BuiltinFn<N>,
+{
+    fn evaluate(
+        &mut self,
+        input: &str,
+        origin: Option<&str>,
+        format: &FormatOptions,
+        numbered: bool,
+    ) -> bool {
+        let printed = self.evaluate_each(input, |number, result| match numbered {
+            true => println!("[{number}] = {}", result.pretty(format)),
+            false => println!("{}", result.pretty(format)),
+        });
+        if let Err(e) = &printed {
+            report_error(origin, input, e, e.span());
+        }
+        printed.is_ok()
+    }
+
+    fn run_script(
+        &mut self,
+        input: &str,
+        origin: Option<&str>,
+        format: &FormatOptions,
+        numbered: bool,
+    ) -> bool {
+        let mut succeeded = true;
+        self.evaluate_all(
+            input,
+            |number, result| match numbered {
+                true => println!("[{number}] = {}", result.pretty(format)),
+                false => println!("{}", result.pretty(format)),
+            },
+            |e| {
+                report_error(origin, input, &e, e.span());
+                succeeded = false;
+            },
+        );
+        succeeded
+    }
+
+    fn export(&self, input: &str, origin: Option<&str>, export: ExportFormat) -> bool {
+        let statements =
+            match Parser::<N>::with_options(input, self.parser_options()).parse_program() {
+                Ok(statements) => statements,
+                Err(e) => {
+                    report_error(origin, input, &e, Some(e.span()));
+                    return false;
+                }
+            };
+        for formula in statements
+            .iter()
+            .filter_map(|statement| export.statement(statement))
+        {
+            println!("{formula}");
+        }
+        true
+    }
+
+    fn differentiate(&self, input: &str, variable: &str) {
+        let statements =
+            match Parser::<N>::with_options(input, self.parser_options()).parse_program() {
+                Ok(statements) => statements,
+                Err(e) => {
+                    report_error(None, input, &e, Some(e.span()));
+                    return;
+                }
+            };
+        let [Statement::Expression(expression)] = statements.as_slice() else {
+            eprintln!("Error: expected a single expression to differentiate");
+            return;
+        };
+        match derivative::diff(expression, variable) {
+            Ok(derivative) => println!("{derivative}"),
+            Err(e) => report_error(None, input, &e, Some(e.span())),
+        }
+    }
+
+    fn history_limit(&self) -> usize {
+        Evaluator::history_limit(self)
+    }
+
+    fn set_history_limit(&mut self, limit: usize) {
+        Evaluator::set_history_limit(self, limit);
+    }
+
+    fn parser_options(&self) -> ParserOptions {
+        Evaluator::parser_options(self)
+    }
+
+    fn set_parser_options(&mut self, options: ParserOptions) {
+        Evaluator::set_parser_options(self, options);
+    }
+
+    fn has_results(&self) -> bool {
+        self.last_result().is_some()
+    }
+
+    fn export_variables(&self) -> Vec<(String, PortableValue)> {
+        self.variables()
+            .iter()
+            .map(|(name, value)| (name.clone(), value.to_portable()))
+            .collect()
+    }
+
+    fn import_variables(&mut self, variables: Vec<(String, PortableValue)>) -> Import {
+        let mut import = Import::default();
+        for (name, value) in variables {
+            let Some(converted) = N::from_portable(&value) else {
+                import.dropped.push(name);
+                continue;
+            };
+            let exact = converted.to_portable() == value;
+            if self.set_variable(name.clone(), converted).is_err() {
+                import.dropped.push(name);
+            } else if !exact {
+                import.rounded.push(name);
+            }
+        }
+        import.dropped.sort();
+        import.rounded.sort();
+        import
+    }
+
+    fn variables(&self, format: &FormatOptions) -> Vec<(String, String)> {
+        sorted_values(Evaluator::variables(self), format)
+    }
+
+    fn constants(&self, format: &FormatOptions) -> Vec<(String, String)> {
+        sorted_values(Evaluator::constants(self), format)
+    }
+
+    fn builtins(&self) -> Vec<(&'static str, Arity)> {
+        let builtins = Evaluator::builtins(self);
+        let mut signatures: Vec<_> = builtins
+            .names()
+            .iter()
+            .filter_map(|name| Some((*name, builtins.arity(name)?)))
+            .collect();
+        signatures.sort_by_key(|(name, _)| *name);
+        signatures
+    }
+
+    fn user_functions(&self) -> Vec<(String, Vec<String>)> {
+        let mut functions: Vec<_> = self
+            .functions()
+            .iter()
+            .map(|(name, function)| (name.clone(), function.parameters().to_vec()))
+            .collect();
+        functions.sort();
+        functions
+    }
+
+    fn remove_variable(&mut self, name: &str) -> bool {
+        Evaluator::remove_variable(self, name).is_some()
+    }
+
+    fn reset(&mut self) {
+        Evaluator::reset(self);
+    }
+
+    fn script(&self) -> String {
+        // every digit in fixed notation, so the value reads back unchanged
+        let format = FormatOptions {
+            notation: Notation::Fixed,
+            ..FormatOptions::default()
+        };
+        let mut script = String::new();
+        for (name, value) in Session::variables(self, &format) {
+            if !(value.contains("NaN") || value.contains("inf")) {
+                script.push_str(&format!("{name} = {value};\n"));
+            }
+        }
+        // bodies are printed rather than copied from the source, which may span several lines
+        // and contain comments
+        for (name, parameters) in self.user_functions() {
+            let body = self.functions()[&name].body();
+            script.push_str(&format!("{name}({}) = {body};\n", parameters.join(", ")));
+        }
+        script
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_calc_lib::format::FormatOptions;
+
+    use super::{Import, Mode, Session};
+
+    /// Runs `input` in a fresh session of mode `from`, then moves it through each of `modes` and
+    /// returns the final session with what every switch reported.
+    fn switch(from: Mode, input: &str, modes: &[Mode]) -> (Box<dyn Session>, Vec<Import>) {
+        let mut session = from.session();
+        assert!(session.run_script(input, None, &FormatOptions::default(), false));
+        let mut imports = Vec::new();
+        for mode in modes {
+            let (converted, import) = mode.convert(session.as_ref());
+            session = converted;
+            imports.push(import);
+        }
+        (session, imports)
+    }
+
+    fn variables(session: &dyn Session) -> Vec<(String, String)> {
+        let format = FormatOptions {
+            precision: None,
+            ..FormatOptions::default()
+        };
+        session.variables(&format)
+    }
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn exact_values_survive_round_trips() {
+        let input = "x = 3^50; y = -7";
+        let (original, _) = switch(Mode::Int, input, &[]);
+        let (session, imports) = switch(Mode::Int, input, &[Mode::Rational, Mode::Int]);
+        assert_eq!(imports, [Import::default(), Import::default()]);
+        assert_eq!(variables(session.as_ref()), variables(original.as_ref()));
+
+        let input = "t = 1/3; q = 5/4";
+        let (original, _) = switch(Mode::Rational, input, &[]);
+        let (session, imports) = switch(Mode::Rational, input, &[Mode::Complex, Mode::Rational]);
+        assert_eq!(imports[0].rounded, names(&["t"]));
+        assert_ne!(variables(session.as_ref()), variables(original.as_ref()));
+
+        let (session, imports) = switch(Mode::Float, "d = 0.1", &[Mode::Rational, Mode::Float]);
+        assert_eq!(imports, [Import::default(), Import::default()]);
+        assert_eq!(variables(session.as_ref()), [("d".into(), "0.1".into())]);
+    }
+
+    #[test]
+    fn lossy_switches_are_reported() {
+        let (_, imports) = switch(Mode::Int, "x = 3^50; y = 2", &[Mode::Float]);
+        assert_eq!(imports[0].rounded, names(&["x"]));
+        let (_, imports) = switch(Mode::Rational, "t = 1/3; h = 1/2", &[Mode::BigFloat]);
+        assert_eq!(imports[0].rounded, names(&["t"]));
+        let (session, imports) = switch(Mode::Complex, "z = 2i; r = 1.5", &[Mode::Int]);
+        assert_eq!(
+            imports[0],
+            Import {
+                dropped: names(&["r", "z"]),
+                rounded: Vec::new(),
+            }
+        );
+        assert!(variables(session.as_ref()).is_empty());
+    }
+
+    #[test]
+    fn settings_carry_over_but_results_do_not() {
+        let mut session = Mode::Float.session();
+        session.set_history_limit(3);
+        assert!(session.run_script("1 + 1", None, &FormatOptions::default(), false));
+        assert!(session.has_results());
+        let (converted, _) = Mode::Rational.convert(session.as_ref());
+        assert_eq!(converted.history_limit(), 3);
+        assert!(!converted.has_results());
+    }
+}
@@ -0,0 +1,82 @@
+use std::path::{Path, PathBuf};
+use std::{env, fs, io};
+
+use rust_calc_lib::format::FormatOptions;
+
+use crate::mode::Session;
+
+/// The directory the REPL keeps its history and sessions in, `$XDG_DATA_HOME/rust-calc` or
+/// `~/.local/share/rust-calc`.
+pub fn data_dir() -> Option<PathBuf> {
+    let data_home = env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))?;
+    Some(data_home.join("rust-calc"))
+}
+
+pub fn history_path() -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join("history.txt"))
+}
+
+/// The file of the named session, or an error if `name` is not a plain file name.
+pub fn session_path(name: &str) -> Result<PathBuf, String> {
+    if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+        return Err(format!("invalid session name `{name}`"));
+    }
+    let dir = data_dir().ok_or("cannot find a data directory, set $XDG_DATA_HOME or $HOME")?;
+    Ok(dir.join("sessions").join(format!("{name}.calc")))
+}
+
+/// Writes the variables and user functions of `session` to `path` as a script.
+pub fn save(session: &dyn Session, path: &Path) -> io::Result<()> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, session.script())
+}
+
+/// Runs the script at `path` statement by statement, printing results and errors, see
+/// [`Session::run_script`]. Returns whether it ran without errors.
+pub fn load(
+    session: &mut dyn Session,
+    path: &Path,
+    format: &FormatOptions,
+    numbered: bool,
+) -> io::Result<bool> {
+    let script = fs::read_to_string(path)?;
+    let origin = path.display().to_string();
+    Ok(session.run_script(&script, Some(&origin), format, numbered))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process};
+
+    use rust_calc_lib::format::FormatOptions;
+
+    use crate::mode::Mode;
+
+    #[test]
+    fn saved_session_loads_back() {
+        let format = FormatOptions::default();
+        let mut session = Mode::BigFloat.session();
+        let input = "k = 2.5\nf(x, y) = x * k + # scaled\n  y - # shifted\n  1\ng(x) = f(x, 1)!";
+        assert!(session.run_script(input, None, &format, false));
+
+        let path = env::temp_dir().join(format!("rust-calc-{}/saved.calc", process::id()));
+        super::save(session.as_ref(), &path).unwrap();
+        let mut loaded = Mode::BigFloat.session();
+        let succeeded = super::load(loaded.as_mut(), &path, &format, false).unwrap();
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+
+        assert!(succeeded, "the saved script is\n{}", session.script());
+        assert_eq!(loaded.script(), session.script());
+        assert!(loaded.run_script("y = g(2) - f(1, 0)", None, &format, false));
+        assert!(
+            loaded
+                .variables(&format)
+                .contains(&("y".into(), "118.5".into()))
+        );
+    }
+}
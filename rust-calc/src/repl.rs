@@ -0,0 +1,268 @@
+use std::path::{Path, PathBuf};
+
+use rust_calc_lib::format::FormatOptions;
+use rustyline::Editor;
+use rustyline::error::ReadlineError;
+use rustyline::history::DefaultHistory;
+
+use crate::helper::CalcHelper;
+use crate::mode::{Mode, Session};
+use crate::persist;
+
+const HELP: &str = "\
+Enter statements separated by `;`, e.g. `x = 2; f(y) = x * y; f(3);`
+`ans` is the last result and `$n` the result shown as `[n]`.
+Products may leave out the `*`, as in `2x`, `3(x + 1)` or `2 sin(x)`.
+`5!` is a factorial, `15%` a percentage, `7 mod 3` or `7 % 3` a remainder and `7 // 3` an
+integer division.
+`#` starts a comment that runs to the end of the line.
+Input continues on the next line while a parenthesis is open or it ends in an operator.
+In scripts and multi-line input a line break also ends a statement, but a line starting with an
+operator such as `-` or `+` continues the line before unless that one ends in `;`.
+
+Commands:
+  :help              show this help
+  :vars              list variables
+  :consts            list constants
+  :funcs             list builtin and user defined functions
+  :clear <name>...   remove variables
+  :reset             remove all variables and user defined functions
+  :precision [n]     show or set the significant digits of results, `full` for all digits
+  :mode [name]       show or switch the numeric mode: float, bigfloat, rational, int, complex
+                     (variables are carried over, user functions and `$n` results are not)
+  :save <file>       write the variables and user defined functions to a file
+  :load <file>       evaluate the statements in a file
+  :diff <expr>, <x>  differentiate an expression with respect to the variable x
+  :quit, exit        leave the REPL";
+
+/// Whether the REPL keeps reading input after a command.
+enum Flow {
+    Continue,
+    Exit,
+}
+
+/// An interactive session reading statements and colon commands line by line.
+pub struct Repl {
+    session: Box<dyn Session>,
+    mode: Mode,
+    format: FormatOptions,
+    /// Where the session is saved on exit, if it was started with `--session`.
+    session_file: Option<PathBuf>,
+}
+
+impl Repl {
+    pub fn new(
+        session: Box<dyn Session>,
+        mode: Mode,
+        format: FormatOptions,
+        session_file: Option<PathBuf>,
+    ) -> Self {
+        Self {
+            session,
+            mode,
+            format,
+            session_file,
+        }
+    }
+
+    pub fn run(mut self) {
+        println!(
+            "RustCalc REPL in {} mode (type ':help' for help, 'exit' to quit)",
+            self.mode
+        );
+        let mut rl = match Editor::<CalcHelper, DefaultHistory>::new() {
+            Ok(res) => res,
+            Err(_) => {
+                eprintln!("Error creating a read-line editor");
+                return;
+            }
+        };
+        rl.set_helper(Some(CalcHelper::default()));
+        let history = persist::history_path();
+        if let Some(history) = &history {
+            // there is no history yet on the first run
+            let _ = rl.load_history(history);
+        }
+
+        loop {
+            if let Some(helper) = rl.helper_mut() {
+                helper.update(self.session.as_ref(), self.mode);
+            }
+            match rl.readline(">>>") {
+                Ok(input) => {
+                    if input.trim().is_empty() {
+                        continue;
+                    }
+                    if input.trim() == "exit" {
+                        break;
+                    }
+                    match rl.add_history_entry(&input) {
+                        Ok(_) => {}
+                        Err(_) => {
+                            eprintln!("Error adding the history entry");
+                            break;
+                        }
+                    };
+
+                    if let Some(command) = input.trim().strip_prefix(':') {
+                        match self.command(command) {
+                            Flow::Continue => continue,
+                            Flow::Exit => break,
+                        }
+                    }
+
+                    self.session.evaluate(&input, None, &self.format, true);
+                }
+                Err(ReadlineError::Eof | ReadlineError::Interrupted) => break,
+                Err(_) => eprintln!("Error: failure reading a line from the repl"),
+            }
+        }
+
+        if let Some(history) = &history {
+            let saved = history
+                .parent()
+                .map_or(Ok(()), std::fs::create_dir_all)
+                .map_err(ReadlineError::from)
+                .and_then(|_| rl.save_history(history));
+            if let Err(e) = saved {
+                eprintln!(
+                    "Error: cannot save the history to {}: {e}",
+                    history.display()
+                );
+            }
+        }
+        if let Some(session_file) = &self.session_file {
+            self.save(session_file);
+        }
+    }
+
+    fn save(&self, path: &Path) {
+        if let Err(e) = persist::save(self.session.as_ref(), path) {
+            eprintln!("Error: cannot save to {}: {e}", path.display());
+        }
+    }
+
+    fn load(&mut self, path: &Path) {
+        if let Err(e) = persist::load(self.session.as_mut(), path, &self.format, true) {
+            eprintln!("Error: cannot load {}: {e}", path.display());
+        }
+    }
+
+    /// Runs a colon command, `command` being the line without the leading colon.
+    fn command(&mut self, command: &str) -> Flow {
+        let mut words = command.split_whitespace();
+        let name = words.next().unwrap_or_default();
+        let arguments: Vec<_> = words.collect();
+        match (name, arguments.as_slice()) {
+            ("help", []) => println!("{HELP}"),
+            ("vars", []) => print_values(&self.session.variables(&self.format), "variables"),
+            ("consts", []) => print_values(&self.session.constants(&self.format), "constants"),
+            ("funcs", []) => self.print_functions(),
+            ("clear", [_, ..]) => {
+                for name in arguments {
+                    if !self.session.remove_variable(name) {
+                        eprintln!("Error: undefined variable `{name}`");
+                    }
+                }
+            }
+            ("reset", []) => self.session.reset(),
+            ("precision", []) => match self.format.precision {
+                Some(precision) => println!("{precision}"),
+                None => println!("full"),
+            },
+            ("precision", ["full"]) => self.format.precision = None,
+            ("precision", [precision]) => match precision.parse() {
+                Ok(precision) if precision > 0 => self.format.precision = Some(precision),
+                _ => eprintln!("Error: invalid precision `{precision}`"),
+            },
+            ("mode", []) => println!("{}", self.mode),
+            ("mode", [mode]) => match mode.parse() {
+                Ok(mode) => self.switch_mode(mode),
+                Err(e) => eprintln!("Error: {e}"),
+            },
+            ("diff", [_, ..]) => self.differentiate(command.trim_start()[name.len()..].trim()),
+            ("save", [file]) => self.save(Path::new(file)),
+            ("load", [file]) => self.load(Path::new(file)),
+            ("quit" | "exit", []) => return Flow::Exit,
+            (
+                "help" | "vars" | "consts" | "funcs" | "clear" | "reset" | "precision" | "mode"
+                | "save" | "load" | "diff" | "quit" | "exit",
+                _,
+            ) => eprintln!("Error: wrong arguments for `:{name}`, type ':help' for usage"),
+            _ => eprintln!("Error: unknown command `:{name}`, type ':help' for a list"),
+        }
+        Flow::Continue
+    }
+
+    /// Runs `:diff`, `arguments` being the expression and the variable separated by a comma.
+    fn differentiate(&self, arguments: &str) {
+        let Some((expression, variable)) = arguments.rsplit_once(',') else {
+            eprintln!("Error: expected `:diff <expression>, <variable>`");
+            return;
+        };
+        let variable = variable.trim();
+        let is_name = variable.starts_with(|c: char| c.is_ascii_alphabetic())
+            && variable
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !is_name {
+            eprintln!("Error: invalid variable name `{variable}`");
+            return;
+        }
+        self.session.differentiate(expression, variable);
+    }
+
+    /// Switches to `mode`, carrying over the variables that can be represented there.
+    fn switch_mode(&mut self, mode: Mode) {
+        let (session, import) = mode.convert(self.session.as_ref());
+        if !import.dropped.is_empty() {
+            eprintln!(
+                "Warning: dropped variables not representable in {mode} mode: {}",
+                import.dropped.join(", ")
+            );
+        }
+        if !import.rounded.is_empty() {
+            eprintln!(
+                "Warning: rounded variables to the precision of {mode} mode: {}",
+                import.rounded.join(", ")
+            );
+        }
+        if !self.session.user_functions().is_empty() {
+            eprintln!("Warning: user functions are not carried over to {mode} mode");
+        }
+        if self.session.has_results() {
+            eprintln!("Warning: results are not carried over to {mode} mode, `$n` starts over");
+        }
+        self.session = session;
+        self.mode = mode;
+    }
+
+    fn print_functions(&self) {
+        println!("builtins:");
+        for (name, arity) in self.session.builtins() {
+            let plural = if arity.max.unwrap_or(arity.min) == 1 {
+                ""
+            } else {
+                "s"
+            };
+            println!("  {name}: {arity} argument{plural}");
+        }
+        let functions = self.session.user_functions();
+        if !functions.is_empty() {
+            println!("user functions:");
+        }
+        for (name, parameters) in functions {
+            println!("  {name}({})", parameters.join(", "));
+        }
+    }
+}
+
+/// Prints `name = value` lines, or a note that there are no `kind`.
+fn print_values(values: &[(String, String)], kind: &str) {
+    if values.is_empty() {
+        println!("no {kind}");
+    }
+    for (name, value) in values {
+        println!("{name} = {value}");
+    }
+}